@@ -0,0 +1,145 @@
+use std::path::PathBuf;
+
+use rt::{models::camera::Camera, utils::output::ImageFormat};
+
+pub const USAGE: &str = "\
+Usage: rt [OPTIONS]
+
+Renders a scene and writes the image to a file, or to the standard output when no output is given.
+
+Options:
+  -s, --scene <SCENE>         built-in scene (spheres, perlin_spheres, light, cornell_box, testing) or path to a scene file [default: cornell_box]
+  -w, --width <PIXELS>        image width
+  -a, --aspect-ratio <RATIO>  image width over height, fractions like 16/9 work too
+  -n, --samples <COUNT>       samples per pixel
+  -d, --max-depth <COUNT>     maximum number of bounces of a ray
+  -b, --brightness <FACTOR>   brightness multiplier of the image
+  -t, --threads <COUNT>       number of render threads [default: all the cores]
+      --seed <SEED>           seed of the random numbers [default: 0]
+      --bvh-stats             compare the median split and SAH trees of the scene on the error output
+  -o, --output <FILE>         output file, the format is picked from its extension
+  -f, --format <FORMAT>       output format: p3, p6, png, png16, hdr or pfm [default: from the output extension, p3 on the standard output]
+  -h, --help                  print this help
+";
+
+pub enum Command {
+    Render(Options),
+    Help,
+}
+
+pub struct Options {
+    pub scene: String,
+    pub width: Option<i32>,
+    pub aspect_ratio: Option<f64>,
+    pub samples_per_pixel: Option<f64>,
+    pub max_depth: Option<f64>,
+    pub brightness: Option<f64>,
+    pub threads: Option<usize>,
+    pub seed: Option<u64>,
+    pub bvh_stats: bool,
+    pub output: Option<PathBuf>,
+    pub format: Option<ImageFormat>,
+}
+
+impl Options {
+    // Overrides the settings of the scene's camera with the ones given on the command line
+    pub fn apply(&self, camera: &mut Camera) {
+        if let Some(width) = self.width {
+            camera.set_image_width(width);
+        }
+        if let Some(aspect_ratio) = self.aspect_ratio {
+            camera.set_aspect_ratio(aspect_ratio);
+        }
+        if let Some(samples_per_pixel) = self.samples_per_pixel {
+            camera.set_samples_per_pixel(samples_per_pixel);
+        }
+        if let Some(max_depth) = self.max_depth {
+            camera.set_max_depth(max_depth);
+        }
+        if let Some(brightness) = self.brightness {
+            camera.set_brightness(brightness);
+        }
+        if let Some(threads) = self.threads {
+            camera.threads = threads;
+        }
+        if let Some(seed) = self.seed {
+            camera.seed = seed;
+        }
+    }
+}
+
+pub fn parse_args(mut args: impl Iterator<Item = String>) -> Result<Command, String> {
+    let mut options: Options = Options {
+        scene: "cornell_box".to_string(),
+        width: None,
+        aspect_ratio: None,
+        samples_per_pixel: None,
+        max_depth: None,
+        brightness: None,
+        threads: None,
+        seed: None,
+        bvh_stats: false,
+        output: None,
+        format: None,
+    };
+
+    while let Some(arg) = args.next() {
+        // Both "--width 400" and "--width=400" are accepted
+        let (flag, inline_value): (String, Option<String>) = match arg.split_once('=') {
+            Some((flag, value)) if flag.starts_with("--") => (flag.to_string(), Some(value.to_string())),
+            _ => (arg.clone(), None),
+        };
+        let mut value = || -> Result<String, String> {
+            inline_value
+                .clone()
+                .or_else(|| args.next())
+                .ok_or_else(|| format!("missing value for {}", flag))
+        };
+
+        match flag.as_str() {
+            "-h" | "--help" => return Ok(Command::Help),
+            "-s" | "--scene" => options.scene = value()?,
+            "-w" | "--width" => options.width = Some(parse_positive(&flag, &value()?)? as i32),
+            "-a" | "--aspect-ratio" => options.aspect_ratio = Some(parse_ratio(&flag, &value()?)?),
+            "-n" | "--samples" => options.samples_per_pixel = Some(parse_positive(&flag, &value()?)? as f64),
+            "-d" | "--max-depth" => options.max_depth = Some(parse_positive(&flag, &value()?)? as f64),
+            "-b" | "--brightness" => options.brightness = Some(parse_ratio(&flag, &value()?)?),
+            "-t" | "--threads" => options.threads = Some(parse_positive(&flag, &value()?)?),
+            "--seed" => {
+                let seed: String = value()?;
+                options.seed = Some(seed.parse::<u64>().map_err(|_| format!("invalid value {:?} for {}", seed, flag))?);
+            }
+            "--bvh-stats" => options.bvh_stats = true,
+            "-o" | "--output" => options.output = Some(PathBuf::from(value()?)),
+            "-f" | "--format" => {
+                let format: String = value()?;
+                options.format = Some(ImageFormat::from_name(&format).ok_or_else(|| format!("unknown format {:?}", format))?);
+            }
+            _ => return Err(format!("unknown argument {:?}", arg)),
+        }
+    }
+
+    Ok(Command::Render(options))
+}
+
+fn parse_positive(flag: &str, value: &str) -> Result<usize, String> {
+    match value.parse::<usize>() {
+        Ok(number) if number > 0 => Ok(number),
+        _ => Err(format!("invalid value {:?} for {}, expected a positive integer", value, flag)),
+    }
+}
+
+fn parse_ratio(flag: &str, value: &str) -> Result<f64, String> {
+    let parsed: Option<f64> = match value.split_once('/') {
+        Some((numerator, denominator)) => numerator
+            .parse::<f64>()
+            .ok()
+            .zip(denominator.parse::<f64>().ok())
+            .map(|(numerator, denominator)| numerator / denominator),
+        None => value.parse::<f64>().ok(),
+    };
+    match parsed {
+        Some(ratio) if ratio.is_finite() && ratio > 0.0 => Ok(ratio),
+        _ => Err(format!("invalid value {:?} for {}, expected a positive number", value, flag)),
+    }
+}
@@ -1,8 +1,20 @@
-use std::{collections::HashMap, rc::Rc};
+mod cli;
+
+use std::{
+    collections::HashMap,
+    env,
+    io::{self, BufWriter, Write},
+    path::Path,
+    process,
+    sync::Arc,
+};
+
+use cli::{parse_args, Command, Options, USAGE};
 
 use rt::{
     models::{
         camera::Camera,
+        image::Image,
         instances::{rotate::Rotate, translate::Translate},
         materials::{
             dielectric::Dielectric,
@@ -10,19 +22,51 @@ use rt::{
             material::{Lambertian, Material},
             metal::Metal,
         },
+        scene::{load_scene, Scene},
+        textures::{
+            checker::CheckerTexture,
+            noise::{NoisePattern, NoiseTexture},
+        },
         objects::{
-            cylinder::Cylinder,
+            bvh::BvhNode,
+            sah_bvh::SahBvh,
             global::{Hittable, HittableList},
             quad::{box_shape, Quad},
             sphere::Sphere,
         },
     },
-    utils::math::{random, random_float, random_vector},
+    utils::{
+        math::{random, random_float, random_float_vector, random_vector},
+        output::{save_image_as, write_image, ImageFormat},
+        sampler::Sampler,
+    },
 };
 
 use glam::{DVec3, DVec3 as Point, DVec3 as Color};
 
 fn main() {
+    let options: Options = match parse_args(env::args().skip(1)) {
+        Ok(Command::Render(options)) => options,
+        Ok(Command::Help) => {
+            print!("{}", USAGE);
+            return;
+        }
+        Err(error) => {
+            eprintln!("rt: {}\n\n{}", error, USAGE);
+            process::exit(2);
+        }
+    };
+
+    // Format picked from the extension of the output file unless given, P3 on the standard output
+    let format: ImageFormat = match (options.format, &options.output) {
+        (Some(format), _) => format,
+        (None, Some(path)) => ImageFormat::from_path(path).unwrap_or_else(|| {
+            eprintln!("rt: unknown image format for {}, use --format", path.display());
+            process::exit(2);
+        }),
+        (None, None) => ImageFormat::P3,
+    };
+
 /////////////////////////// ADD OTHER REFRACTION INDEXES IF YOU WANT TO ////////////////////////////////
     // Set of materials and their refraction index
     let materials: Vec<&str> = vec!["air", "water", "glass", "diamond"];
@@ -43,65 +87,106 @@ fn main() {
 
     let mut world: HittableList = HittableList::new();
 
-    // Camera Scenes
-    // let mut camera: Camera =  spheres(&mut world, refraction_indexes);
-    let mut camera: Camera = cornell_box(&mut world, refraction_indexes);
-    // let mut camera: Camera = light(&mut world, refraction_indexes);
-    // let mut camera: Camera = testing(&mut world, refraction_indexes);
+    // Camera Scenes, a built-in one or a scene file
+    let mut camera: Camera = match options.scene.as_str() {
+        "spheres" => spheres(&mut world, refraction_indexes),
+        "perlin_spheres" => perlin_spheres(&mut world),
+        "cornell_box" => cornell_box(&mut world, refraction_indexes),
+        "light" => light(&mut world, refraction_indexes),
+        "testing" => testing(&mut world, refraction_indexes),
+        path => {
+            let scene: Scene = load_scene(Path::new(path)).unwrap_or_else(|error| {
+                eprintln!("rt: failed to load the scene {}: {}", path, error);
+                process::exit(1);
+            });
+            world = scene.world;
+            scene.camera
+        }
+    };
+    options.apply(&mut camera);
 
-    camera.render(&mut world);
+    // Wrapping the world in a bounding volume hierarchy, so each ray only tests the objects it may hit
+    // The median split tree is only built to be compared with, when asked
+    if options.bvh_stats {
+        eprintln!("Median split BVH: {}", BvhNode::new(world.clone()).stats());
+    }
+    let world: SahBvh = SahBvh::new(world, 4);
+    if options.bvh_stats {
+        eprintln!("SAH BVH:          {}", world.stats());
+    }
+
+    let image: Image = camera.render(&world);
+
+    let saved: io::Result<()> = match &options.output {
+        Some(path) => save_image_as(&image, path, format),
+        None => {
+            let mut out: BufWriter<io::StdoutLock> = BufWriter::new(io::stdout().lock());
+            write_image(&image, format, &mut out).and_then(|_| out.flush())
+        }
+    };
+    if let Err(error) = saved {
+        eprintln!("rt: failed to write the image: {}", error);
+        process::exit(1);
+    }
 }
 
 pub fn spheres(world: &mut HittableList, refraction_indexes: HashMap<&str, f64>) -> Camera {
-    let ground: Rc<Lambertian> = Rc::new(Lambertian::new(Color::new(0.5, 0.5, 0.5)));
-    world.add(Rc::new(Sphere::new(
+    let checker: Arc<CheckerTexture> = Arc::new(CheckerTexture::from_colors(
+        0.32,
+        Color::new(0.2, 0.3, 0.1),
+        Color::new(0.9, 0.9, 0.9),
+    ));
+    let ground: Arc<Lambertian> = Arc::new(Lambertian::from_texture(checker));
+    world.add(Arc::new(Sphere::new(
         Point::new(0.0, -1000.0, 0.0),
         1000.0,
         ground,
     )));
 
+    // Fixed seed, so the spheres land at the same spots on every run
+    let mut sampler: Sampler = Sampler::new(0);
+
     for i in -11..11 {
         for j in -11..11 {
-            let choose_mat: f64 = random_float();
+            let choose_mat: f64 = random_float(&mut sampler);
             let center: Point = Point::new(
-                i as f64 + 0.9 * random_float(),
+                i as f64 + 0.9 * random_float(&mut sampler),
                 0.2,
-                j as f64 + 0.9 * random_float(),
+                j as f64 + 0.9 * random_float(&mut sampler),
             );
 
             if (center - Point::new(4.0, 0.2, 0.0)).length() > 0.9 {
-                let material: Rc<dyn Material> = if choose_mat < 0.8 {
-                    let albedo: Color = Color::new(random_float(), random_float(), random_float())
-                        * Color::new(random_float(), random_float(), random_float());
-                    Rc::new(Lambertian::new(albedo))
+                let material: Arc<dyn Material> = if choose_mat < 0.8 {
+                    let albedo: Color = random_float_vector(&mut sampler) * random_float_vector(&mut sampler);
+                    Arc::new(Lambertian::new(albedo))
                 } else if choose_mat < 0.95 {
-                    let albedo: Color = random_vector(0.5, 1.0);
-                    let fuzz: f64 = random(0.0, 0.5);
-                    Rc::new(Metal::new(albedo, fuzz))
+                    let albedo: Color = random_vector(&mut sampler, 0.5, 1.0);
+                    let fuzz: f64 = random(&mut sampler, 0.0, 0.5);
+                    Arc::new(Metal::new(albedo, fuzz))
                 } else {
-                    Rc::new(Dielectric::new(refraction_indexes["glass"]))
+                    Arc::new(Dielectric::new(refraction_indexes["glass"]))
                 };
-                world.add(Rc::new(Sphere::new(center, 0.2, material)));
+                world.add(Arc::new(Sphere::new(center, 0.2, material)));
             }
         }
     }
 
-    let material_1: Rc<Dielectric> = Rc::new(Dielectric::new(refraction_indexes["glass"]));
-    world.add(Rc::new(Sphere::new(
+    let material_1: Arc<Dielectric> = Arc::new(Dielectric::new(refraction_indexes["glass"]));
+    world.add(Arc::new(Sphere::new(
         Point::new(0.0, 1.0, 0.0),
         1.0,
         material_1,
     )));
 
-    let material_2: Rc<Lambertian> = Rc::new(Lambertian::new(Color::new(0.4, 0.2, 0.1)));
-    world.add(Rc::new(Sphere::new(
+    let material_2: Arc<Lambertian> = Arc::new(Lambertian::new(Color::new(0.4, 0.2, 0.1)));
+    world.add(Arc::new(Sphere::new(
         Point::new(-4.0, 1.0, 0.0),
         1.0,
         material_2,
     )));
 
-    let material_3: Rc<Metal> = Rc::new(Metal::new(Color::new(0.7, 0.6, 0.5), 0.0));
-    world.add(Rc::new(Sphere::new(
+    let material_3: Arc<Metal> = Arc::new(Metal::new(Color::new(0.7, 0.6, 0.5), 0.0));
+    world.add(Arc::new(Sphere::new(
         Point::new(4.0, 1.0, 0.0),
         1.0,
         material_3,
@@ -144,30 +229,30 @@ pub fn spheres(world: &mut HittableList, refraction_indexes: HashMap<&str, f64>)
 }
 
 pub fn light(world: &mut HittableList, _refraction_indexes: HashMap<&str, f64>) -> Camera {
-    let material_3: Rc<Lambertian> = Rc::new(Lambertian::new(Color::new(0.7, 0.6, 0.5)));
-    world.add(Rc::new(Sphere::new(
+    let material_3: Arc<Lambertian> = Arc::new(Lambertian::new(Color::new(0.7, 0.6, 0.5)));
+    world.add(Arc::new(Sphere::new(
         Point::new(0.0, -1000.0, 0.0),
         1000.0,
         material_3,
     )));
 
-    let material_3: Rc<Lambertian> = Rc::new(Lambertian::new(Color::new(0.7, 0.6, 0.5)));
-    world.add(Rc::new(Sphere::new(
+    let material_3: Arc<Lambertian> = Arc::new(Lambertian::new(Color::new(0.7, 0.6, 0.5)));
+    world.add(Arc::new(Sphere::new(
         Point::new(0.0, 1.0, 0.0),
         1.0,
         material_3,
     )));
 
-    let diff_light: Rc<DiffuseLight> = Rc::new(DiffuseLight::new(Color::new(4.0, 4.0, 4.0)));
-    world.add(Rc::new(Quad::new(
+    let diff_light: Arc<DiffuseLight> = Arc::new(DiffuseLight::new(Color::new(4.0, 4.0, 4.0)));
+    world.add(Arc::new(Quad::new(
         Point::new(3.0, 1.0, -2.0),
         DVec3::new(2.0, 0.0, 0.0),
         DVec3::new(0.0, 2.0, 0.0),
         diff_light,
     )));
 
-    let diff_light1: Rc<DiffuseLight> = Rc::new(DiffuseLight::new(Color::new(0.0, 3.0, 0.2)));
-    world.add(Rc::new(Sphere::new(
+    let diff_light1: Arc<DiffuseLight> = Arc::new(DiffuseLight::new(Color::new(0.0, 3.0, 0.2)));
+    world.add(Arc::new(Sphere::new(
         Point::new(-3.0, 3.0, 2.0),
         1.0,
         diff_light1,
@@ -209,40 +294,107 @@ pub fn light(world: &mut HittableList, _refraction_indexes: HashMap<&str, f64>)
     camera
 }
 
-pub fn cornell_box(world: &mut HittableList, refraction_indexes: HashMap<&str, f64>) -> Camera {
-    let red: Rc<Lambertian> = Rc::new(Lambertian::new(Color::new(0.65, 0.05, 0.05)));
-    let white: Rc<Lambertian> = Rc::new(Lambertian::new(Color::new(0.73, 0.73, 0.73)));
-    let green: Rc<Lambertian> = Rc::new(Lambertian::new(Color::new(0.12, 0.45, 0.15)));
-    let light: Rc<DiffuseLight> = Rc::new(DiffuseLight::new(Color::new(15.0, 15.0, 15.0)));
-    // let light: Rc<DiffuseLight> = Rc::new(DiffuseLight::new(Color::new(7.0, 7.0, 7.0)));
-    // let metal: Rc<Metal> = Rc::new(Metal::new(Color::new(0.5, 0.5, 0.5), 0.0));
+pub fn perlin_spheres(world: &mut HittableList) -> Camera {
+    // Fixed seed, so the noise is the same on every run
+    let mut sampler: Sampler = Sampler::new(0);
+
+    let marble: Arc<NoiseTexture> = Arc::new(NoiseTexture::new(NoisePattern::Marble, 4.0, &mut sampler));
+    world.add(Arc::new(Sphere::new(
+        Point::new(0.0, -1000.0, 0.0),
+        1000.0,
+        Arc::new(Lambertian::from_texture(marble)),
+    )));
+
+    let wood: Arc<NoiseTexture> = Arc::new(NoiseTexture::with_colors(
+        NoisePattern::Wood,
+        6.0,
+        Color::new(0.3, 0.15, 0.05),
+        Color::new(0.75, 0.5, 0.25),
+        &mut sampler,
+    ));
+    world.add(Arc::new(Sphere::new(
+        Point::new(0.0, 2.0, -2.3),
+        2.0,
+        Arc::new(Lambertian::from_texture(wood)),
+    )));
+
+    let turbulence: Arc<NoiseTexture> = Arc::new(NoiseTexture::new(NoisePattern::Turbulence, 2.0, &mut sampler));
+    world.add(Arc::new(Sphere::new(
+        Point::new(0.0, 2.0, 2.3),
+        2.0,
+        Arc::new(Lambertian::from_texture(turbulence)),
+    )));
+
+    let image_width: i32 = 800;
+    let aspect_ratio: f64 = 16.0 / 9.0;
+    let camera_fov: f64 = 30.0;
+    let camera_background: Color = Color::new(0.7, 0.8, 1.0);
+
+    // camera position
+    let look_from: Point = Point::new(13.0, 2.0, 3.0);
+    let look_at: Point = Point::new(0.0, 1.0, 0.0);
+    let vup: DVec3 = DVec3::new(0.0, 1.0, 0.0);
+
+    // depth of field
+    let defocus_angle: f64 = 0.0;
+    let focus_dist: f64 = 10.0;
+
+    let samples_per_pixel: f64 = 100.0;
+    let max_depth: f64 = 50.0;
+    let brightness: f64 = 1.0;
+
+    let camera: Camera = Camera::new(
+        camera_fov,
+        image_width,
+        look_from,
+        look_at,
+        vup,
+        aspect_ratio,
+        samples_per_pixel,
+        max_depth,
+        defocus_angle,
+        focus_dist,
+        camera_background,
+        brightness,
+    );
+
+    camera
+}
+
+pub fn cornell_box(world: &mut HittableList, _refraction_indexes: HashMap<&str, f64>) -> Camera {
+    let red: Arc<Lambertian> = Arc::new(Lambertian::new(Color::new(0.65, 0.05, 0.05)));
+    let white: Arc<Lambertian> = Arc::new(Lambertian::new(Color::new(0.73, 0.73, 0.73)));
+    let green: Arc<Lambertian> = Arc::new(Lambertian::new(Color::new(0.12, 0.45, 0.15)));
+    let light: Arc<DiffuseLight> = Arc::new(DiffuseLight::new(Color::new(15.0, 15.0, 15.0)));
+    // let light: Arc<DiffuseLight> = Arc::new(DiffuseLight::new(Color::new(7.0, 7.0, 7.0)));
+    // let metal: Arc<Metal> = Arc::new(Metal::new(Color::new(0.5, 0.5, 0.5), 0.0));
 
     // Adding walls
-    world.add(Rc::new(Quad::new(
+    world.add(Arc::new(Quad::new(
         Point::new(555.0, 0.0, 0.0),
         DVec3::new(0.0, 555.0, 0.0),
         DVec3::new(0.0, 0.0, 555.0),
         green,
     )));
-    world.add(Rc::new(Quad::new(
+    world.add(Arc::new(Quad::new(
         Point::new(0.00, 0.0, 0.0),
         DVec3::new(0.0, 555.0, 0.0),
         DVec3::new(0.0, 0.0, 555.0),
         red,
     )));
-    world.add(Rc::new(Quad::new(
+    world.add(Arc::new(Quad::new(
         Point::new(0.0, 0.0, 0.0),
         DVec3::new(555.0, 0.0, 0.0),
         DVec3::new(0.0, 0.0, 555.0),
         white.clone(),
     )));
-    world.add(Rc::new(Quad::new(
+    world.add(Arc::new(Quad::new(
         Point::new(555.0, 555.0, 555.0),
         DVec3::new(-555.0, 0.0, 0.0),
         DVec3::new(0.0, 0.0, -555.0),
         white.clone(),
     )));
-    world.add(Rc::new(Quad::new(
+    world.add(Arc::new(Quad::new(
         Point::new(0.0, 0.0, 555.0),
         DVec3::new(555.0, 0.0, 0.0),
         DVec3::new(0.0, 555.0, 0.0),
@@ -250,13 +402,13 @@ pub fn cornell_box(world: &mut HittableList, refraction_indexes: HashMap<&str, f
     )));
 
     // Adding light
-    world.add(Rc::new(Quad::new(
+    world.add(Arc::new(Quad::new(
         Point::new(344.0, 554.0, 332.0),
         DVec3::new(-130.0, 0.0, 0.0),
         DVec3::new(0.0, 0.0, -105.0),
         light,
     )));
-    // world.add(Rc::new(Quad::new(
+    // world.add(Arc::new(Quad::new(
     //     Point::new(113.0, 554.0, 127.0),
     //     DVec3::new(330.0, 0.0, 0.0),
     //     DVec3::new(0.0, 0.0, 305.0),
@@ -264,23 +416,23 @@ pub fn cornell_box(world: &mut HittableList, refraction_indexes: HashMap<&str, f
     // ))); // big light
 
     // Adding boxes
-    let mut box_1: Rc<dyn Hittable> = box_shape(Point::new(0.0, 0.0, 0.0), Point::new(165.0, 330.0, 165.0), white.clone()); // left box
-    box_1 = Rc::new(Rotate::new(box_1, 15.0));
-    box_1 = Rc::new(Translate::new(box_1, DVec3::new(265.0, 0.0, 295.0)));
+    let mut box_1: Arc<dyn Hittable> = box_shape(Point::new(0.0, 0.0, 0.0), Point::new(165.0, 330.0, 165.0), white.clone()); // left box
+    box_1 = Arc::new(Rotate::new(box_1, 15.0));
+    box_1 = Arc::new(Translate::new(box_1, DVec3::new(265.0, 0.0, 295.0)));
 
-    let mut box_2: Rc<dyn Hittable> = box_shape(
+    let mut box_2: Arc<dyn Hittable> = box_shape(
         Point::new(0.0, 0.0, 0.0),
         Point::new(165.0, 165.0, 165.0),
         white.clone(),
     ); // right box
-    box_2 = Rc::new(Rotate::new(box_2, -18.0));
-    box_2 = Rc::new(Translate::new(box_2, DVec3::new(130.0, 0.0, 65.0)));
+    box_2 = Arc::new(Rotate::new(box_2, -18.0));
+    box_2 = Arc::new(Translate::new(box_2, DVec3::new(130.0, 0.0, 65.0)));
 
-    // let glass: Rc<Dielectric> = Rc::new(Dielectric::new(refraction_indexes["glass"]));
-    // let glass_sphere: Rc<Sphere> =
-    //     Rc::new(Sphere::new(Point::new(190.0, 290.0, 190.0), 90.0, glass));
+    // let glass: Arc<Dielectric> = Arc::new(Dielectric::new(_refraction_indexes["glass"]));
+    // let glass_sphere: Arc<Sphere> =
+    //     Arc::new(Sphere::new(Point::new(190.0, 290.0, 190.0), 90.0, glass));
 
-    // let cylinder: Rc<Cylinder> = Rc::new(Cylinder::new(
+    // let cylinder: Arc<Cylinder> = Arc::new(Cylinder::new(
     //     Point::new(400.0, 0.0, 250.0),
     //     DVec3::new(0.0, 1.0, 0.0),
     //     60.0,
@@ -330,31 +482,31 @@ pub fn cornell_box(world: &mut HittableList, refraction_indexes: HashMap<&str, f
 }
 
 pub fn testing(world: &mut HittableList, _refraction_indexes: HashMap<&str, f64>) -> Camera {
-    let ground: Rc<Lambertian> = Rc::new(Lambertian::new(Color::new(1.0, 0.0, 0.0)));
-    world.add(Rc::new(Sphere::new(
+    let ground: Arc<Lambertian> = Arc::new(Lambertian::new(Color::new(1.0, 0.0, 0.0)));
+    world.add(Arc::new(Sphere::new(
         Point::new(0.0, -1000.0, 0.0),
         1000.0,
         ground,
     )));
 
-    // let material: Rc<Metal> = Rc::new(Metal::new(Color::new(0.5, 0.5, 0.5), 0.0));
+    // let material: Arc<Metal> = Arc::new(Metal::new(Color::new(0.5, 0.5, 0.5), 0.0));
 
-    // let box_2: Rc<dyn Hittable> = box_shape(
+    // let box_2: Arc<dyn Hittable> = box_shape(
     //     Point::new(0.0, 4.0, 0.0),
     //     Point::new(3.0, 3.0, 3.0),
     //     material.clone(),
     // ); // right box
 
-    let sphere: Rc<Sphere> = Rc::new(Sphere::new(
+    let sphere: Arc<Sphere> = Arc::new(Sphere::new(
         Point::new(2.0, 0.0, 0.0),
         1.0,
-        Rc::new(Metal::new(Color::new(1.0, 1.0, 0.3), 0.0)),
+        Arc::new(Metal::new(Color::new(1.0, 1.0, 0.3), 0.0)),
     ));
 
-    let sphere2: Rc<Sphere> = Rc::new(Sphere::new(
+    let sphere2: Arc<Sphere> = Arc::new(Sphere::new(
         Point::new(5.0, 0.0, 0.0),
         1.0,
-        Rc::new(Metal::new(Color::new(1.0, 1.0, 0.3), 0.4)),
+        Arc::new(Metal::new(Color::new(1.0, 1.0, 0.3), 0.4)),
     ));
     // world.add(mirror);
     // world.add(box_2);
@@ -1,15 +1,30 @@
+use std::{
+    ops::Range,
+    sync::atomic::{AtomicUsize, Ordering},
+    thread,
+};
+
 use glam::{DVec3, DVec3 as Point, DVec3 as Color};
 
 use crate::utils::{
-    color::convert_to_rgb,
-    math::{degrees_to_radians, random_float, random_in_unit_disk},
+    math::{degrees_to_radians, power_heuristic, random_float, random_in_unit_disk},
+    sampler::Sampler,
 };
 
 use super::{
-    objects::global::{HitRecord, Hittable, HittableList},
+    image::Image,
+    lights::light::{LightList, LightSample},
+    objects::{
+        constant_medium::Haze,
+        global::{HitRecord, Hittable},
+        medium::{set_medium_record, track, transmittance, MediumEvent, MediumSegment},
+    },
     ray::Ray,
 };
 
+// Pixels of each finished tile, along with the index of the tile
+type RenderedTiles = Vec<(usize, Vec<Color>)>;
+
 pub struct Camera {
     pub look_from: Point,
     pub look_at: Point,
@@ -20,7 +35,23 @@ pub struct Camera {
     pub v: DVec3,
     pub w: DVec3,
     pub background: Color,
+    // Number of worker threads rendering tiles, 1 renders on the calling thread
+    pub threads: usize,
+    // Width and height in pixels of the square tiles handed to the workers
+    pub tile_size: i32,
+    // Seed of the render, the same seed always gives the same image
+    pub seed: u64,
+    // Medium filling the whole scene, none by default
+    pub haze: Option<Haze>,
+    // Diffuse surfaces and volumes send shadow rays to the emissive spheres and quads of the world,
+    // instead of only finding them when a bounce happens to hit them
+    pub sample_lights: bool,
+    // Point, spot and directional lights, not part of the world, always sampled whatever `sample_lights`
+    pub lights: LightList,
+    // Bounces every path makes before Russian roulette may end it, paths still stop at the max depth
+    pub roulette_depth: f64,
     image_width: i32,
+    aspect_ratio: f64,
     samples_per_pixel: f64,
     max_depth: f64,
     pixel_samples_scale: f64,
@@ -30,12 +61,14 @@ pub struct Camera {
     pixel_delta_y: DVec3,
     first_pixel: Point,
     defocus_angle: f64,
+    focus_dist: f64,
     defocus_disk_u: DVec3,
     defocus_disk_v: DVec3,
     brightness: f64,
 }
 
 impl Camera {
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
         fov: f64,
         image_width: i32,
@@ -50,111 +83,319 @@ impl Camera {
         background: Color,
         brightness: f64,
     ) -> Self {
-        let mut image_height: i32 = (image_width as f64 / aspect_ratio) as i32;
-
-        if image_height < 1 {
-            image_height = 1;
-        };
-
-        let theta: f64 = degrees_to_radians(fov);
-        let h: f64 = (theta / 2.0).tan();
-
-        let center: Point = look_from;
-        let viewport_height: f64 = 2.0 * h * focus_dist;
-        let viewport_width = viewport_height * (image_width as f64 / image_height as f64);
-
-        let w: DVec3 = (look_from - look_at).normalize();
-        let u: DVec3 = (vup.cross(w)).normalize();
-        let v: DVec3 = w.cross(u);
-
-        let viewport_x: DVec3 = viewport_width * u;
-        let viewport_y: DVec3 = viewport_height * -v;
-        let viewport_upper_left: Point =
-            center - (focus_dist * w) - viewport_x / 2.0 - viewport_y / 2.0;
+        let threads: usize = thread::available_parallelism().map_or(1, |n| n.get());
 
-        let pixel_delta_x: DVec3 = viewport_x / image_width as f64;
-        let pixel_delta_y: DVec3 = viewport_y / image_height as f64;
-        let first_pixel: Point = viewport_upper_left + 0.5 * (pixel_delta_x + pixel_delta_y);
-        let pixel_samples_scale: f64 = 1.0 / samples_per_pixel;
-
-        let defocus_radius: f64 = focus_dist * (degrees_to_radians(defocus_angle / 2.0)).tan();
-        let defocus_disk_u: DVec3 = u * defocus_radius;
-        let defocus_disk_v: DVec3 = v * defocus_radius;
-
-        Self {
+        let mut camera: Camera = Self {
             look_from,
             look_at,
             fov,
-            image_height,
+            image_height: 0,
             image_width,
-            viewport_height,
-            center,
-            pixel_delta_x,
-            pixel_delta_y,
-            first_pixel,
+            aspect_ratio,
+            viewport_height: 0.0,
+            center: look_from,
+            pixel_delta_x: DVec3::ZERO,
+            pixel_delta_y: DVec3::ZERO,
+            first_pixel: Point::ZERO,
             samples_per_pixel,
-            pixel_samples_scale,
+            pixel_samples_scale: 0.0,
             max_depth,
             vup,
-            u,
-            v,
-            w,
+            u: DVec3::ZERO,
+            v: DVec3::ZERO,
+            w: DVec3::ZERO,
             defocus_angle,
-            defocus_disk_u,
-            defocus_disk_v,
+            focus_dist,
+            defocus_disk_u: DVec3::ZERO,
+            defocus_disk_v: DVec3::ZERO,
             background,
             brightness,
+            threads,
+            tile_size: 32,
+            seed: 0,
+            haze: None,
+            sample_lights: true,
+            lights: LightList::new(),
+            roulette_depth: 5.0,
+        };
+        camera.initialize();
+        camera
+    }
+
+    // Computes the viewport and everything derived from the settings, called again whenever one of them changes
+    fn initialize(&mut self) {
+        self.image_height = (self.image_width as f64 / self.aspect_ratio) as i32;
+
+        if self.image_height < 1 {
+            self.image_height = 1;
+        };
+
+        let theta: f64 = degrees_to_radians(self.fov);
+        let h: f64 = (theta / 2.0).tan();
+
+        self.center = self.look_from;
+        self.viewport_height = 2.0 * h * self.focus_dist;
+        let viewport_width = self.viewport_height * (self.image_width as f64 / self.image_height as f64);
+
+        self.w = (self.look_from - self.look_at).normalize();
+        self.u = (self.vup.cross(self.w)).normalize();
+        self.v = self.w.cross(self.u);
+
+        let viewport_x: DVec3 = viewport_width * self.u;
+        let viewport_y: DVec3 = self.viewport_height * -self.v;
+        let viewport_upper_left: Point =
+            self.center - (self.focus_dist * self.w) - viewport_x / 2.0 - viewport_y / 2.0;
+
+        self.pixel_delta_x = viewport_x / self.image_width as f64;
+        self.pixel_delta_y = viewport_y / self.image_height as f64;
+        self.first_pixel = viewport_upper_left + 0.5 * (self.pixel_delta_x + self.pixel_delta_y);
+        self.pixel_samples_scale = 1.0 / self.samples_per_pixel;
+
+        let defocus_radius: f64 = self.focus_dist * (degrees_to_radians(self.defocus_angle / 2.0)).tan();
+        self.defocus_disk_u = self.u * defocus_radius;
+        self.defocus_disk_v = self.v * defocus_radius;
+    }
+
+    pub fn image_width(&self) -> i32 {
+        self.image_width
+    }
+
+    pub fn image_height(&self) -> i32 {
+        self.image_height
+    }
+
+    pub fn set_image_width(&mut self, image_width: i32) {
+        self.image_width = image_width;
+        self.initialize();
+    }
+
+    pub fn set_aspect_ratio(&mut self, aspect_ratio: f64) {
+        self.aspect_ratio = aspect_ratio;
+        self.initialize();
+    }
+
+    pub fn set_samples_per_pixel(&mut self, samples_per_pixel: f64) {
+        self.samples_per_pixel = samples_per_pixel;
+        self.initialize();
+    }
+
+    pub fn set_max_depth(&mut self, max_depth: f64) {
+        self.max_depth = max_depth;
+    }
+
+    pub fn set_brightness(&mut self, brightness: f64) {
+        self.brightness = brightness;
+    }
+
+    pub fn render(&self, world: &dyn Hittable) -> Image {
+        let mut lights: LightList = if self.sample_lights {
+            LightList::from_world(world)
+        } else {
+            LightList::new()
+        };
+        for light in &self.lights.lights {
+            lights.add(light.clone());
         }
+        self.render_tiles(world, &lights)
     }
 
-    pub fn render(&mut self, world: &mut HittableList) {
-        println!("P3");
-        println!("{} {}", self.image_width, self.image_height);
-        println!("255");
-        for j in 0..self.image_height {
-            eprint!("\rScanlines remaining {}   ", self.image_height - j);
-            for i in 0..self.image_width {
-                let mut pixel_color = Color::new(0.0, 0.0, 0.0);
+    // Splits the image in tiles and hands them to a pool of workers, each pixel only depends on its own samples
+    // so the image is the same whatever the number of threads and the order tiles get picked in.
+    fn render_tiles(&self, world: &dyn Hittable, lights: &LightList) -> Image {
+        let tile_size: i32 = self.tile_size.max(1);
+        let mut tiles: Vec<(i32, i32)> = Vec::new();
+        for tile_y in (0..self.image_height).step_by(tile_size as usize) {
+            for tile_x in (0..self.image_width).step_by(tile_size as usize) {
+                tiles.push((tile_x, tile_y));
+            }
+        }
 
-                for _ in 0..self.samples_per_pixel as i32 {
-                    let ray: Ray = self.get_ray(i, j);
-                    pixel_color += self.ray_color(ray, self.max_depth, world)
+        let next_tile: AtomicUsize = AtomicUsize::new(0);
+        let tiles_done: AtomicUsize = AtomicUsize::new(0);
+        let worker = || {
+            let mut rendered: RenderedTiles = Vec::new();
+            loop {
+                let index: usize = next_tile.fetch_add(1, Ordering::Relaxed);
+                if index >= tiles.len() {
+                    return rendered;
                 }
-                convert_to_rgb(self.brightness * (pixel_color * self.pixel_samples_scale));
+                let (tile_x, tile_y) = tiles[index];
+                rendered.push((index, self.render_tile(tile_x, tile_y, tile_size, world, lights)));
+
+                let done: usize = tiles_done.fetch_add(1, Ordering::Relaxed) + 1;
+                eprint!("\rTiles remaining {}   ", tiles.len() - done);
+            }
+        };
+
+        let threads: usize = self.threads.clamp(1, tiles.len().max(1));
+        let rendered: RenderedTiles = if threads == 1 {
+            worker()
+        } else {
+            thread::scope(|scope| {
+                let handles: Vec<thread::ScopedJoinHandle<RenderedTiles>> =
+                    (0..threads).map(|_| scope.spawn(worker)).collect();
+                handles
+                    .into_iter()
+                    .flat_map(|handle| handle.join().expect("render worker panicked"))
+                    .collect()
+            })
+        };
+        eprintln!();
+
+        // Stitching the tiles back together
+        let width: usize = self.image_width as usize;
+        let mut image: Image = Image::new(width, self.image_height as usize);
+        for (index, tile) in rendered {
+            let (tile_x, tile_y) = tiles[index];
+            let tile_width: usize = (tile_size.min(self.image_width - tile_x)) as usize;
+            for (row, line) in tile.chunks(tile_width).enumerate() {
+                let start: usize = (tile_y as usize + row) * width + tile_x as usize;
+                image.pixels[start..start + tile_width].copy_from_slice(line);
             }
         }
+        image
     }
 
-    pub fn ray_color(&self, ray: Ray, max_depth: f64, world: &mut HittableList) -> Color {
-        if max_depth <= 0.0 {
-            return Color::new(0.0, 0.0, 0.0);
+    fn render_tile(&self, tile_x: i32, tile_y: i32, tile_size: i32, world: &dyn Hittable, lights: &LightList) -> Vec<Color> {
+        let mut tile: Vec<Color> = Vec::with_capacity((tile_size * tile_size) as usize);
+        for j in tile_y..(tile_y + tile_size).min(self.image_height) {
+            for i in tile_x..(tile_x + tile_size).min(self.image_width) {
+                tile.push(self.render_pixel(i, j, world, lights));
+            }
+        }
+        tile
+    }
+
+    fn render_pixel(&self, i: i32, j: i32, world: &dyn Hittable, lights: &LightList) -> Color {
+        let mut pixel_color = Color::new(0.0, 0.0, 0.0);
+        let mut sampler: Sampler = Sampler::for_pixel(self.seed, i, j);
+
+        for _ in 0..self.samples_per_pixel as i32 {
+            let ray: Ray = self.get_ray(i, j, &mut sampler);
+            pixel_color += self.ray_color(ray, self.max_depth, world, lights, &mut sampler)
+        }
+        self.brightness * (pixel_color * self.pixel_samples_scale)
+    }
+
+    // Follows the path of the ray bounce after bounce, in a loop so deep paths don't grow the stack.
+    // Lights are reached both by scattering and by sampling them at each diffuse or glossy hit, the two are
+    // combined with multiple importance sampling.
+    pub fn ray_color(&self, ray: Ray, max_depth: f64, world: &dyn Hittable, lights: &LightList, sampler: &mut Sampler) -> Color {
+        let mut ray: Ray = ray;
+        let mut color: Color = Color::ZERO;
+        // Fraction of the light at the current hit that makes it back to the camera
+        let mut throughput: Color = Color::ONE;
+        // Density the ray was scattered with when the hit it comes from sampled the lights too, the emission
+        // it finds is then weighted against it
+        let mut scattering_pdf: Option<f64> = None;
+        let mut depth: f64 = 0.0;
+
+        while depth < max_depth {
+            let mut rec: HitRecord = HitRecord::new();
+
+            let mut hit: bool = world.hit(ray, 0.001..f64::INFINITY, &mut rec);
+
+            // The ray may be scattered or absorbed by the volumes and the haze it goes through before
+            // reaching the surface or leaving the scene
+            let t_max: f64 = if hit { rec.t } else { f64::INFINITY };
+            let segments: Vec<MediumSegment> = self.media(ray, 0.001..t_max, world);
+            match track(&segments, sampler) {
+                MediumEvent::Passed(weight) => throughput *= weight,
+                MediumEvent::Absorbed(emitted) => return color + throughput * emitted,
+                MediumEvent::Scattered { t, weight, phase_function } => {
+                    throughput *= weight;
+                    set_medium_record(&mut rec, ray, t, phase_function);
+                    hit = true;
+                }
+            }
+
+            // Color of the background of the Scene
+            if !hit {
+                return color + throughput * self.background;
+            }
+
+            // if the ray intersects in an object, it displays it
+            let mut scattered: Ray = Ray::new(DVec3::new(0.0, 0.0, 0.0), DVec3::new(0.0, 0.0, 0.0));
+            let mut color_attenuation: Color = Color::new(0.0, 0.0, 0.0);
+            let mut color_from_emission: Color = rec.mat.color_emitted(rec.u, rec.v, rec.point);
+            if let (true, Some(pdf)) = (rec.sampled_light, scattering_pdf) {
+                color_from_emission *= power_heuristic(pdf, lights.pdf_value(ray.origin, ray.direction));
+            }
+            color += throughput * color_from_emission;
+
+            // Materials which spread the light also get the light coming straight from one of the lights,
+            // even when the scattered ray gets absorbed (like a fuzzy reflection going under the surface)
+            let sampled_lights: bool = !rec.mat.is_delta() && !lights.is_empty();
+            if sampled_lights {
+                color += throughput * self.direct_light(ray, &rec, world, lights, sampler);
+            }
+
+            if !rec
+                .mat
+                .scatter(ray, &rec, &mut color_attenuation, &mut scattered, sampler)
+            {
+                return color;
+            }
+            scattering_pdf = sampled_lights.then(|| rec.mat.scattering_pdf(ray, &rec, scattered.direction));
+
+            throughput *= color_attenuation;
+            ray = scattered;
+            depth += 1.0;
+
+            // Russian roulette: past `roulette_depth` bounces, dim paths are ended at random, and the ones
+            // going on are brightened by as much so the image stays the same on average
+            if depth >= self.roulette_depth {
+                let survival: f64 = throughput.max_element().min(1.0);
+                if random_float(sampler) >= survival {
+                    return color;
+                }
+                throughput /= survival;
+            }
         }
+        color
+    }
 
-        let mut rec: HitRecord = HitRecord::new();
+    // Light of a light picked in the list, scattered by the material of the hit toward the ray
+    fn direct_light(&self, ray: Ray, rec: &HitRecord, world: &dyn Hittable, lights: &LightList, sampler: &mut Sampler) -> Color {
+        let Some(sample) = lights.sample(rec.point, sampler) else {
+            return Color::ZERO;
+        };
+        let scattering: Color = rec.mat.eval(ray, rec, sample.direction);
+        if scattering == Color::ZERO || sample.pdf <= 0.0 {
+            return Color::ZERO;
+        }
 
-        // Color of the background of the Scene
-        if !world.hit(ray, 0.001..f64::INFINITY, &mut rec) {
-            return self.background;
+        let shadow_ray: Ray = Ray::new(rec.point, sample.direction);
+        let mut blocker: HitRecord = HitRecord::new();
+        if world.hit(shadow_ray, 0.001..sample.distance - 0.001, &mut blocker) {
+            return Color::ZERO;
         }
+        // Volumes only let part of the light through, the sun being as far as the haze goes
+        let transmittance: Color = transmittance(&self.media(shadow_ray, 0.001..sample.distance - 0.001, world), sampler);
 
-        // if the ray intersects in an object, it displays it
-        let mut scattered: Ray = Ray::new(DVec3::new(0.0, 0.0, 0.0), DVec3::new(0.0, 0.0, 0.0));
-        let mut color_attenuation: Color = Color::new(0.0, 0.0, 0.0);
-        let color_from_emission: Color = rec.mat.color_emitted(rec.u, rec.v, rec.point);
+        let LightSample { direction, radiance, pdf, hittable, .. } = sample;
+        // Weighted with the density of the whole list, like the emission found by scattering, so both
+        // weights always sum to 1 even where lights overlap. Nothing else finds points, spots and the sun.
+        let weight: f64 = if hittable {
+            power_heuristic(lights.pdf_value(rec.point, direction), rec.mat.scattering_pdf(ray, rec, direction))
+        } else {
+            1.0
+        };
+        weight * transmittance * scattering * radiance / pdf
+    }
 
-        if !rec
-            .mat
-            .scatter(ray, &rec, &mut color_attenuation, &mut scattered)
-        {
-            return color_from_emission;
+    // Parts of the ray going through the volumes of the world and the haze, within the interval
+    fn media<'a>(&'a self, ray: Ray, ray_t: Range<f64>, world: &'a dyn Hittable) -> Vec<MediumSegment<'a>> {
+        let mut segments: Vec<MediumSegment> = Vec::new();
+        world.media(ray, ray_t.clone(), &mut segments);
+        if let Some(haze) = &self.haze {
+            segments.push(haze.segment(ray, ray_t.end));
         }
-        let color_from_scatter: Color =
-            color_attenuation * self.ray_color(scattered, max_depth - 1.0, world);
-        return color_from_emission + color_from_scatter;
+        segments
     }
 
-    fn get_ray(&self, i: i32, j: i32) -> Ray {
-        let offset: DVec3 = self.sample_square();
+    fn get_ray(&self, i: i32, j: i32, sampler: &mut Sampler) -> Ray {
+        let offset: DVec3 = self.sample_square(sampler);
         let pixel_sample: Point = self.first_pixel
             + ((i as f64 + offset.x) * self.pixel_delta_x)
             + ((j as f64 + offset.y) * self.pixel_delta_y);
@@ -162,18 +403,18 @@ impl Camera {
         let ray_origin: Point = if self.defocus_angle <= 0.0 {
             self.center
         } else {
-            self.defocus_disk_sample()
+            self.defocus_disk_sample(sampler)
         };
         let ray_direction: DVec3 = pixel_sample - ray_origin;
         Ray::new(ray_origin, ray_direction)
     }
 
-    fn sample_square(&self) -> DVec3 {
-        DVec3::new(random_float() - 0.5, random_float() - 0.5, 0.0)
+    fn sample_square(&self, sampler: &mut Sampler) -> DVec3 {
+        DVec3::new(random_float(sampler) - 0.5, random_float(sampler) - 0.5, 0.0)
     }
 
-    fn defocus_disk_sample(&self) -> DVec3 {
-        let p: DVec3 = random_in_unit_disk();
+    fn defocus_disk_sample(&self, sampler: &mut Sampler) -> DVec3 {
+        let p: DVec3 = random_in_unit_disk(sampler);
         self.center + (p[0] * self.defocus_disk_u) + (p[1] * self.defocus_disk_v)
     }
 }
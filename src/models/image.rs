@@ -0,0 +1,40 @@
+use glam::DVec3 as Color;
+
+// Rendered picture, pixels hold the linear (not gamma corrected, not clamped) radiance, row by row from the top left
+#[derive(Clone, Debug, PartialEq)]
+pub struct Image {
+    pub width: usize,
+    pub height: usize,
+    pub pixels: Vec<Color>,
+}
+
+impl Image {
+    pub fn new(width: usize, height: usize) -> Self {
+        Self {
+            width,
+            height,
+            pixels: vec![Color::new(0.0, 0.0, 0.0); width * height],
+        }
+    }
+
+    pub fn from_pixels(width: usize, height: usize, pixels: Vec<Color>) -> Self {
+        assert_eq!(pixels.len(), width * height, "pixel count doesn't match the image size");
+        Self {
+            width,
+            height,
+            pixels,
+        }
+    }
+
+    pub fn get(&self, x: usize, y: usize) -> Color {
+        self.pixels[y * self.width + x]
+    }
+
+    pub fn set(&mut self, x: usize, y: usize, color: Color) {
+        self.pixels[y * self.width + x] = color;
+    }
+
+    pub fn rows(&self) -> std::slice::Chunks<'_, Color> {
+        self.pixels.chunks(self.width.max(1))
+    }
+}
@@ -1,2 +1,3 @@
 pub mod translate;
-pub mod rotate;
\ No newline at end of file
+pub mod rotate;
+pub mod transform;
\ No newline at end of file
@@ -1,68 +1,130 @@
-use std::{ops::Range, rc::Rc};
+use std::{ops::Range, sync::Arc};
 
 use glam::{DVec3, DVec3 as Point};
 
 use crate::{
     models::{
-        objects::global::{HitRecord, Hittable},
+        objects::{
+            aabb::Aabb,
+            global::{HitRecord, Hittable},
+            medium::MediumSegment,
+        },
         ray::Ray,
     },
-    utils::math::degrees_to_radians,
+    utils::{math::degrees_to_radians, sampler::Sampler},
 };
 
 pub struct Rotate {
-    pub object: Rc<dyn Hittable>,
+    pub object: Arc<dyn Hittable>,
     pub cos_theta: f64,
     pub sin_theta: f64,
+    bbox: Aabb,
 }
 
 impl Hittable for Rotate {
     fn hit(&self, ray: Ray, ray_t: Range<f64>, rec: &mut HitRecord) -> bool {
-        // Transform the ray from 'world' space to 'object' space
-        let origin: Point = Point::new(
-            (self.cos_theta * ray.origin.x) - (self.sin_theta * ray.origin.z),
-            ray.origin.y,
-            (self.sin_theta * ray.origin.x) + (self.cos_theta * ray.origin.z),
-        );
-
-        let direction: DVec3 = DVec3::new(
-            (self.cos_theta * ray.direction.x) - (self.sin_theta * ray.direction.z),
-            ray.direction.y,
-            (self.sin_theta * ray.direction.x) + (self.cos_theta * ray.direction.z),
-        );
-
-        let rotated_ray: Ray = Ray::new(origin, direction);
+        let rotated_ray: Ray = self.object_ray(ray);
 
         if !self.object.hit(rotated_ray, ray_t, rec) {
             return false;
         }
 
-        rec.point = Point::new(
-            (self.cos_theta * rec.point.x) + (self.sin_theta * rec.point.z),
-            rec.point.y,
-            (-self.sin_theta * rec.point.x) + (self.cos_theta * rec.point.z),
-        );
+        rec.point = self.world_vector(rec.point);
 
-        rec.normal = DVec3::new(
-            (self.cos_theta * rec.normal.x) + (self.sin_theta * rec.normal.z),
-            rec.normal.y,
-            (-self.sin_theta * rec.normal.x) + (self.cos_theta * rec.normal.z),
-        );
+        rec.normal = self.world_vector(rec.normal);
 
         true
     }
+
+    fn bounding_box(&self) -> Aabb {
+        self.bbox.clone()
+    }
+
+    // A rotation keeps the solid angles, the densities are the same in both spaces
+    fn is_light(&self) -> bool {
+        self.object.is_light()
+    }
+
+    fn pdf_value(&self, origin: Point, direction: DVec3) -> f64 {
+        let object_ray: Ray = self.object_ray(Ray::new(origin, direction));
+        self.object.pdf_value(object_ray.origin, object_ray.direction)
+    }
+
+    fn random(&self, origin: Point, sampler: &mut Sampler) -> DVec3 {
+        let object_origin: Point = self.object_ray(Ray::new(origin, DVec3::ZERO)).origin;
+        self.world_vector(self.object.random(object_origin, sampler))
+    }
+
+    // The lights of the object are turned the same way
+    fn lights(&self) -> Vec<Arc<dyn Hittable>> {
+        self.object
+            .lights()
+            .into_iter()
+            .map(|light| Arc::new(Rotate::from_sin_cos(light, self.sin_theta, self.cos_theta)) as Arc<dyn Hittable>)
+            .collect()
+    }
+
+    fn has_media(&self) -> bool {
+        self.object.has_media()
+    }
+
+    fn media<'a>(&'a self, ray: Ray, ray_t: Range<f64>, segments: &mut Vec<MediumSegment<'a>>) {
+        self.object.media(self.object_ray(ray), ray_t, segments);
+    }
 }
 
 impl Rotate {
-    pub fn new(object: Rc<dyn Hittable>, angle: f64) -> Self {
+    pub fn new(object: Arc<dyn Hittable>, angle: f64) -> Self {
         let radians: f64 = degrees_to_radians(angle);
-        let sin_theta: f64 = radians.sin();
-        let cos_theta: f64 = radians.cos();
+        Self::from_sin_cos(object, radians.sin(), radians.cos())
+    }
+
+    fn from_sin_cos(object: Arc<dyn Hittable>, sin_theta: f64, cos_theta: f64) -> Self {
+        // Rotate every corner of the object's box to 'world' space and keep the extrema
+        let mut min: Point = Point::splat(f64::INFINITY);
+        let mut max: Point = Point::splat(f64::NEG_INFINITY);
+        for corner in object.bounding_box().corners() {
+            let rotated: Point = Point::new(
+                (cos_theta * corner.x) + (sin_theta * corner.z),
+                corner.y,
+                (-sin_theta * corner.x) + (cos_theta * corner.z),
+            );
+            min = min.min(rotated);
+            max = max.max(rotated);
+        }
+        let bbox: Aabb = Aabb::from_points(min, max);
 
         Self {
             object,
             cos_theta,
             sin_theta,
+            bbox,
         }
     }
+
+    // Transform the ray from 'world' space to 'object' space
+    fn object_ray(&self, ray: Ray) -> Ray {
+        let origin: Point = Point::new(
+            (self.cos_theta * ray.origin.x) - (self.sin_theta * ray.origin.z),
+            ray.origin.y,
+            (self.sin_theta * ray.origin.x) + (self.cos_theta * ray.origin.z),
+        );
+
+        let direction: DVec3 = DVec3::new(
+            (self.cos_theta * ray.direction.x) - (self.sin_theta * ray.direction.z),
+            ray.direction.y,
+            (self.sin_theta * ray.direction.x) + (self.cos_theta * ray.direction.z),
+        );
+
+        Ray::new(origin, direction)
+    }
+
+    // Transform a point or a vector from 'object' space back to 'world' space
+    fn world_vector(&self, vector: DVec3) -> DVec3 {
+        DVec3::new(
+            (self.cos_theta * vector.x) + (self.sin_theta * vector.z),
+            vector.y,
+            (-self.sin_theta * vector.x) + (self.cos_theta * vector.z),
+        )
+    }
 }
@@ -0,0 +1,154 @@
+use std::{ops::Range, sync::Arc};
+
+use glam::{DMat3, DMat4, DVec3, DVec3 as Point};
+
+use crate::{
+    models::{
+        objects::{
+            aabb::Aabb,
+            global::{HitRecord, Hittable},
+            medium::MediumSegment,
+        },
+        ray::Ray,
+    },
+    utils::{math::degrees_to_radians, sampler::Sampler},
+};
+
+// Places an object with any affine matrix, going from 'object' space to 'world' space.
+// Matrices compose with `*`, the rightmost one is applied first:
+//     Transform::new(object, translation(offset) * rotation(axis, angle) * scale(factors))
+// The matrix has to be invertible, so no scale factor can be zero: `new` gives None otherwise.
+pub struct Transform {
+    pub object: Arc<dyn Hittable>,
+    matrix: DMat4,
+    inverse: DMat4,
+    // Inverse transpose, normals have to stay perpendicular to the surface under non uniform scales
+    normal_matrix: DMat3,
+    bbox: Aabb,
+}
+
+// Below this the matrix flattens the object too much to be inverted reliably
+const MIN_DETERMINANT: f64 = 1e-12;
+
+impl Transform {
+    pub fn new(object: Arc<dyn Hittable>, matrix: DMat4) -> Option<Self> {
+        if !matrix.is_finite() || matrix.determinant().abs() <= MIN_DETERMINANT {
+            return None;
+        }
+        let inverse: DMat4 = matrix.inverse();
+        let normal_matrix: DMat3 = DMat3::from_mat4(inverse).transpose();
+
+        // Transform every corner of the object's box to 'world' space and keep the extrema
+        let mut min: Point = Point::splat(f64::INFINITY);
+        let mut max: Point = Point::splat(f64::NEG_INFINITY);
+        for corner in object.bounding_box().corners() {
+            let transformed: Point = matrix.transform_point3(corner);
+            min = min.min(transformed);
+            max = max.max(transformed);
+        }
+        let bbox: Aabb = Aabb::from_points(min, max);
+
+        Some(Self {
+            object,
+            matrix,
+            inverse,
+            normal_matrix,
+            bbox,
+        })
+    }
+
+    pub fn matrix(&self) -> DMat4 {
+        self.matrix
+    }
+
+    // The direction is not normalized, so the distances along the ray are the same in both spaces
+    fn object_ray(&self, ray: Ray) -> Ray {
+        Ray::new(
+            self.inverse.transform_point3(ray.origin),
+            self.inverse.transform_vector3(ray.direction),
+        )
+    }
+}
+
+// Rotation of `angle` degrees around any axis, counterclockwise when the axis points toward the viewer
+pub fn rotation(axis: DVec3, angle: f64) -> DMat4 {
+    DMat4::from_axis_angle(axis.normalize(), degrees_to_radians(angle))
+}
+
+// Scale along x, y and z, a negative factor mirrors the object
+pub fn scale(factors: DVec3) -> DMat4 {
+    DMat4::from_scale(factors)
+}
+
+pub fn translation(offset: DVec3) -> DMat4 {
+    DMat4::from_translation(offset)
+}
+
+// Moves the object's origin to `from` and turns it so its +z axis points at `to`, with its +y axis toward `up`
+pub fn look_at(from: Point, to: Point, up: DVec3) -> DMat4 {
+    let z_axis: DVec3 = (to - from).normalize();
+    let x_axis: DVec3 = up.cross(z_axis).normalize();
+    let y_axis: DVec3 = z_axis.cross(x_axis);
+    DMat4::from_cols(
+        x_axis.extend(0.0),
+        y_axis.extend(0.0),
+        z_axis.extend(0.0),
+        from.extend(1.0),
+    )
+}
+
+impl Hittable for Transform {
+    fn hit(&self, ray: Ray, ray_t: Range<f64>, rec: &mut HitRecord) -> bool {
+        let object_ray: Ray = self.object_ray(ray);
+
+        if !self.object.hit(object_ray, ray_t, rec) {
+            return false;
+        }
+
+        let outward_normal: DVec3 = if rec.front_face { rec.normal } else { -rec.normal };
+        rec.point = self.matrix.transform_point3(rec.point);
+        rec.normal = (self.normal_matrix * outward_normal).normalize();
+        rec.set_face_normal(ray);
+        true
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        self.bbox.clone()
+    }
+
+    fn is_light(&self) -> bool {
+        self.object.is_light()
+    }
+
+    // A scale or a shear squeezes the directions, the density over solid angle changes with how much the
+    // matrix stretches the cone around `direction`: |det A| / |A w|^3 for the linear part A of the inverse
+    fn pdf_value(&self, origin: Point, direction: DVec3) -> f64 {
+        let object_ray: Ray = self.object_ray(Ray::new(origin, direction.normalize()));
+        let stretch: f64 = object_ray.direction.length();
+        let jacobian: f64 = DMat3::from_mat4(self.inverse).determinant().abs() / (stretch * stretch * stretch);
+        self.object.pdf_value(object_ray.origin, object_ray.direction) * jacobian
+    }
+
+    fn random(&self, origin: Point, sampler: &mut Sampler) -> DVec3 {
+        let direction: DVec3 = self.object.random(self.inverse.transform_point3(origin), sampler);
+        self.matrix.transform_vector3(direction)
+    }
+
+    // The lights of the object are placed with the same matrix
+    fn lights(&self) -> Vec<Arc<dyn Hittable>> {
+        self.object
+            .lights()
+            .into_iter()
+            .filter_map(|light| Transform::new(light, self.matrix))
+            .map(|light| Arc::new(light) as Arc<dyn Hittable>)
+            .collect()
+    }
+
+    fn has_media(&self) -> bool {
+        self.object.has_media()
+    }
+
+    fn media<'a>(&'a self, ray: Ray, ray_t: Range<f64>, segments: &mut Vec<MediumSegment<'a>>) {
+        self.object.media(self.object_ray(ray), ray_t, segments);
+    }
+}
@@ -1,16 +1,19 @@
-use std::{ops::Range, rc::Rc};
+use std::{ops::Range, sync::Arc};
 
-use glam::DVec3;
+use glam::{DVec3, DVec3 as Point};
 
-use crate::models::{objects::global::{HitRecord, Hittable}, ray::Ray};
+use crate::{
+    models::{objects::{aabb::Aabb, global::{HitRecord, Hittable}, medium::MediumSegment}, ray::Ray},
+    utils::sampler::Sampler,
+};
 
 pub struct Translate {
-    pub object: Rc<dyn Hittable>,
+    pub object: Arc<dyn Hittable>,
     pub offset: DVec3,
 }
 
 impl Translate {
-    pub fn new(object: Rc<dyn Hittable>, offset: DVec3) -> Self {
+    pub fn new(object: Arc<dyn Hittable>, offset: DVec3) -> Self {
         Self { object, offset }
     }
 }
@@ -24,6 +27,43 @@ impl Hittable for Translate {
         }
 
         rec.point += self.offset;
+        // Same normal, but the side is decided again against the ray in 'world' space
+        rec.normal = if rec.front_face { rec.normal } else { -rec.normal };
+        rec.set_face_normal(ray);
         true
     }
+
+    fn bounding_box(&self) -> Aabb {
+        self.object.bounding_box().translate(self.offset)
+    }
+
+    // Directions don't change, only the origin is moved
+    fn is_light(&self) -> bool {
+        self.object.is_light()
+    }
+
+    fn pdf_value(&self, origin: Point, direction: DVec3) -> f64 {
+        self.object.pdf_value(origin - self.offset, direction)
+    }
+
+    fn random(&self, origin: Point, sampler: &mut Sampler) -> DVec3 {
+        self.object.random(origin - self.offset, sampler)
+    }
+
+    // The lights of the object are moved the same way
+    fn lights(&self) -> Vec<Arc<dyn Hittable>> {
+        self.object
+            .lights()
+            .into_iter()
+            .map(|light| Arc::new(Translate::new(light, self.offset)) as Arc<dyn Hittable>)
+            .collect()
+    }
+
+    fn has_media(&self) -> bool {
+        self.object.has_media()
+    }
+
+    fn media<'a>(&'a self, ray: Ray, ray_t: Range<f64>, segments: &mut Vec<MediumSegment<'a>>) {
+        self.object.media(Ray::new(ray.origin - self.offset, ray.direction), ray_t, segments);
+    }
 }
\ No newline at end of file
@@ -0,0 +1,48 @@
+use std::sync::Arc;
+
+use glam::{DVec3, DVec3 as Point};
+
+use crate::{
+    models::{
+        objects::global::{HitRecord, Hittable},
+        ray::Ray,
+    },
+    utils::sampler::Sampler,
+};
+
+use super::light::{Light, LightSample};
+
+// Emissive shape, sampled with the directions it gives toward itself
+pub struct AreaLight {
+    pub shape: Arc<dyn Hittable>,
+}
+
+impl AreaLight {
+    pub fn new(shape: Arc<dyn Hittable>) -> Self {
+        Self { shape }
+    }
+}
+
+impl Light for AreaLight {
+    fn sample(&self, point: Point, sampler: &mut Sampler) -> Option<LightSample> {
+        let direction: DVec3 = self.shape.random(point, sampler).normalize();
+        let pdf: f64 = self.shape.pdf_value(point, direction);
+        // The emission is the one of the point of the shape seen in that direction
+        let mut rec: HitRecord = HitRecord::new();
+        if pdf <= 0.0 || !self.shape.hit(Ray::new(point, direction), 0.001..f64::INFINITY, &mut rec) {
+            return None;
+        }
+
+        Some(LightSample {
+            direction,
+            distance: rec.t,
+            radiance: rec.mat.color_emitted(rec.u, rec.v, rec.point),
+            pdf,
+            hittable: true,
+        })
+    }
+
+    fn pdf(&self, point: Point, direction: DVec3) -> f64 {
+        self.shape.pdf_value(point, direction)
+    }
+}
@@ -0,0 +1,61 @@
+use glam::{DVec3, DVec3 as Color, DVec3 as Point};
+
+use crate::utils::{
+    math::{degrees_to_radians, random_in_cone},
+    onb::Onb,
+    sampler::Sampler,
+};
+
+use super::light::{Light, LightSample};
+
+// Light coming from infinitely far away, all along `direction`, like the sun. Its strength doesn't change
+// with the distance. With an angular diameter (in degrees, the sun is about 0.53) the light comes from
+// a small disk of the sky instead of a single direction, which softens the shadows.
+pub struct DirectionalLight {
+    // Where the light goes, away from the sun
+    pub direction: DVec3,
+    // Color times intensity, the light a surface facing the sun gets
+    pub intensity: Color,
+    cos_theta_max: f64,
+}
+
+impl DirectionalLight {
+    pub fn new(direction: DVec3, color: Color, intensity: f64) -> Self {
+        Self::with_angular_diameter(direction, color, intensity, 0.0)
+    }
+
+    pub fn with_angular_diameter(direction: DVec3, color: Color, intensity: f64, angular_diameter: f64) -> Self {
+        let half_angle: f64 = degrees_to_radians(angular_diameter.clamp(0.0, 180.0)) / 2.0;
+        Self {
+            direction: direction.normalize(),
+            intensity: color * intensity,
+            cos_theta_max: half_angle.cos().min(1.0),
+        }
+    }
+}
+
+impl Light for DirectionalLight {
+    fn sample(&self, _point: Point, sampler: &mut Sampler) -> Option<LightSample> {
+        let to_light: DVec3 = -self.direction;
+        // Spread uniformly over the disk, its radiance being the intensity over its solid angle the two
+        // cancel out
+        let direction: DVec3 = if self.cos_theta_max < 1.0 {
+            Onb::new(to_light).transform(random_in_cone(self.cos_theta_max, sampler))
+        } else {
+            to_light
+        };
+
+        Some(LightSample {
+            direction,
+            distance: f64::INFINITY,
+            radiance: self.intensity,
+            pdf: 1.0,
+            hittable: false,
+        })
+    }
+
+    // The sun isn't part of the world, scattered rays never find it
+    fn pdf(&self, _point: Point, _direction: DVec3) -> f64 {
+        0.0
+    }
+}
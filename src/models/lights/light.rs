@@ -0,0 +1,80 @@
+use std::sync::Arc;
+
+use glam::{DVec3, DVec3 as Color, DVec3 as Point};
+
+use crate::{
+    models::objects::global::Hittable,
+    utils::{math::random_int, sampler::Sampler},
+};
+
+use super::area_light::AreaLight;
+
+// Light reaching a point from a sampled light
+pub struct LightSample {
+    // Unit direction toward the light
+    pub direction: DVec3,
+    // Distance to the sampled point of the light, the shadow ray stops just before it
+    pub distance: f64,
+    pub radiance: Color,
+    // Probability density of the direction, over solid angle. Lights found in a single direction, like
+    // points and spots, give 1 with their radiance already divided by the squared distance.
+    pub pdf: f64,
+    // Whether scattered rays can find the light too, the sample is then weighted against them
+    pub hittable: bool,
+}
+
+// Something the camera can send shadow rays to
+pub trait Light: Send + Sync {
+    fn sample(&self, point: Point, sampler: &mut Sampler) -> Option<LightSample>;
+    // Density of `sample` picking `direction` from `point`
+    fn pdf(&self, point: Point, direction: DVec3) -> f64;
+}
+
+// The lights of a scene, picked uniformly when a point asks for one
+#[derive(Clone, Default)]
+pub struct LightList {
+    pub lights: Vec<Arc<dyn Light>>,
+}
+
+impl LightList {
+    pub fn new() -> Self {
+        Self { lights: Vec::new() }
+    }
+
+    // Every emissive shape of the world which can be sampled
+    pub fn from_world(world: &dyn Hittable) -> Self {
+        let mut list: LightList = LightList::new();
+        for shape in world.lights() {
+            list.add(Arc::new(AreaLight::new(shape)));
+        }
+        list
+    }
+
+    pub fn add(&mut self, light: Arc<dyn Light>) {
+        self.lights.push(light);
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.lights.is_empty()
+    }
+
+    // Density of picking `direction` from `point`, through any of the lights
+    pub fn pdf_value(&self, point: Point, direction: DVec3) -> f64 {
+        if self.lights.is_empty() {
+            return 0.0;
+        }
+        let total: f64 = self.lights.iter().map(|light| light.pdf(point, direction)).sum();
+        total / self.lights.len() as f64
+    }
+
+    pub fn sample(&self, point: Point, sampler: &mut Sampler) -> Option<LightSample> {
+        if self.lights.is_empty() {
+            return None;
+        }
+        let count: usize = self.lights.len();
+        let index: usize = (random_int(sampler, 0.0, (count - 1) as f64) as usize).min(count - 1);
+        let mut sample: LightSample = self.lights[index].sample(point, sampler)?;
+        sample.pdf /= count as f64;
+        Some(sample)
+    }
+}
@@ -0,0 +1,5 @@
+pub mod light;
+pub mod area_light;
+pub mod point_light;
+pub mod spot_light;
+pub mod directional_light;
@@ -0,0 +1,44 @@
+use glam::{DVec3, DVec3 as Color, DVec3 as Point};
+
+use crate::utils::sampler::Sampler;
+
+use super::light::{Light, LightSample};
+
+// Light given off by a single point in every direction, fading with the square of the distance
+pub struct PointLight {
+    pub position: Point,
+    // Color times intensity, the radiance a point at distance 1 gets
+    pub intensity: Color,
+}
+
+impl PointLight {
+    pub fn new(position: Point, color: Color, intensity: f64) -> Self {
+        Self {
+            position,
+            intensity: color * intensity,
+        }
+    }
+}
+
+impl Light for PointLight {
+    fn sample(&self, point: Point, _sampler: &mut Sampler) -> Option<LightSample> {
+        let to_light: DVec3 = self.position - point;
+        let distance: f64 = to_light.length();
+        if distance <= 0.0 {
+            return None;
+        }
+
+        Some(LightSample {
+            direction: to_light / distance,
+            distance,
+            radiance: self.intensity / (distance * distance),
+            pdf: 1.0,
+            hittable: false,
+        })
+    }
+
+    // No scattered ray can find a point
+    fn pdf(&self, _point: Point, _direction: DVec3) -> f64 {
+        0.0
+    }
+}
@@ -0,0 +1,63 @@
+use glam::{DVec3, DVec3 as Color, DVec3 as Point};
+
+use crate::utils::{math::degrees_to_radians, sampler::Sampler};
+
+use super::{
+    light::{Light, LightSample},
+    point_light::PointLight,
+};
+
+// Point light shining in a cone toward `direction`. It's at full intensity up to `inner_angle` degrees
+// from the axis, then fades smoothly to nothing at `outer_angle`.
+pub struct SpotLight {
+    pub light: PointLight,
+    pub direction: DVec3,
+    cos_inner: f64,
+    cos_outer: f64,
+}
+
+impl SpotLight {
+    pub fn new(position: Point, direction: DVec3, color: Color, intensity: f64, inner_angle: f64, outer_angle: f64) -> Self {
+        let outer_angle: f64 = outer_angle.clamp(0.0, 180.0);
+        let inner_angle: f64 = inner_angle.clamp(0.0, outer_angle);
+        Self {
+            light: PointLight::new(position, color, intensity),
+            direction: direction.normalize(),
+            cos_inner: degrees_to_radians(inner_angle).cos(),
+            cos_outer: degrees_to_radians(outer_angle).cos(),
+        }
+    }
+
+    // Spot pointing from `position` to `target`
+    pub fn looking_at(position: Point, target: Point, color: Color, intensity: f64, inner_angle: f64, outer_angle: f64) -> Self {
+        Self::new(position, target - position, color, intensity, inner_angle, outer_angle)
+    }
+
+    // 1 inside the inner cone, 0 outside of the outer one, smoothstep in between
+    fn falloff(&self, cos_theta: f64) -> f64 {
+        if cos_theta >= self.cos_inner {
+            return 1.0;
+        }
+        if cos_theta <= self.cos_outer {
+            return 0.0;
+        }
+        let x: f64 = (cos_theta - self.cos_outer) / (self.cos_inner - self.cos_outer);
+        x * x * (3.0 - 2.0 * x)
+    }
+}
+
+impl Light for SpotLight {
+    fn sample(&self, point: Point, sampler: &mut Sampler) -> Option<LightSample> {
+        let mut sample: LightSample = self.light.sample(point, sampler)?;
+        let falloff: f64 = self.falloff(-sample.direction.dot(self.direction));
+        if falloff <= 0.0 {
+            return None;
+        }
+        sample.radiance *= falloff;
+        Some(sample)
+    }
+
+    fn pdf(&self, _point: Point, _direction: DVec3) -> f64 {
+        0.0
+    }
+}
@@ -2,7 +2,10 @@ use glam::{DVec3, DVec3 as Color};
 
 use crate::{
     models::{objects::global::HitRecord, ray::Ray},
-    utils::math::{random_float, reflect, reflectance, refract},
+    utils::{
+        math::{random_float, reflect, reflectance, refract},
+        sampler::Sampler,
+    },
 };
 
 use super::material::Material;
@@ -24,6 +27,7 @@ impl Material for Dielectric {
         rec: &HitRecord,
         color_attenuation: &mut DVec3,
         scattered: &mut Ray,
+        sampler: &mut Sampler,
     ) -> bool {
         *color_attenuation = Color::new(1.0, 1.0, 1.0);
 
@@ -39,7 +43,7 @@ impl Material for Dielectric {
         let sin_tetha:f64 = (1.0 - cos_tetha * cos_tetha).sqrt();
 
         let cannot_refract:bool = ri * sin_tetha > 1.0;
-        let direction:DVec3 = if cannot_refract || reflectance(cos_tetha, ri) > random_float(){
+        let direction:DVec3 = if cannot_refract || reflectance(cos_tetha, ri) > random_float(sampler){
             reflect(unit_direction, rec.normal)
         } else {
             refract(unit_direction, rec.normal, ri)
@@ -1,24 +1,33 @@
+use std::sync::Arc;
+
 use glam::DVec3 as Color;
 
-use crate::models::{objects::global::HitRecord, ray::Ray};
+use crate::{models::{objects::global::HitRecord, ray::Ray, textures::texture::{SolidColor, Texture}}, utils::sampler::Sampler};
 
 use super::material::Material;
 
 pub struct DiffuseLight {
-    pub color: Color,
+    pub texture: Arc<dyn Texture>,
 }
 
 impl DiffuseLight {
     pub fn new(color: Color) -> Self {
-        Self { color }
+        Self::from_texture(Arc::new(SolidColor::new(color)))
+    }
+
+    pub fn from_texture(texture: Arc<dyn Texture>) -> Self {
+        Self { texture }
     }
 }
 
 impl Material for DiffuseLight {
-    fn scatter(&self, _ray_in: Ray, _rec: &HitRecord, _color_attenuation: &mut Color, _scattered: &mut Ray) -> bool {
+    fn scatter(&self, _ray_in: Ray, _rec: &HitRecord, _color_attenuation: &mut Color, _scattered: &mut Ray, _sampler: &mut Sampler) -> bool {
         false
     }
-    fn color_emitted(&self, _u:f64, _v:f64, _point: Color) -> Color {
-        self.color
+    fn color_emitted(&self, u:f64, v:f64, point: Color) -> Color {
+        self.texture.value(u, v, point)
+    }
+    fn is_emissive(&self) -> bool {
+        true
     }
 }
\ No newline at end of file
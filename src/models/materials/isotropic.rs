@@ -0,0 +1,49 @@
+use std::{f64::consts::PI, sync::Arc};
+
+use glam::{DVec3, DVec3 as Color};
+
+use crate::{
+    models::{
+        objects::global::HitRecord,
+        ray::Ray,
+        textures::texture::{SolidColor, Texture},
+    },
+    utils::{math::random_unit_vector, sampler::Sampler},
+};
+
+use super::material::Material;
+
+// Phase function of the participating media, light is scattered the same way in every direction
+pub struct Isotropic {
+    pub albedo: Arc<dyn Texture>,
+}
+
+impl Isotropic {
+    pub fn new(color: Color) -> Self {
+        Self::from_texture(Arc::new(SolidColor::new(color)))
+    }
+
+    pub fn from_texture(texture: Arc<dyn Texture>) -> Self {
+        Self { albedo: texture }
+    }
+}
+
+impl Material for Isotropic {
+    fn scatter(&self, _ray_in: Ray, rec: &HitRecord, color_attenuation: &mut Color, scattered: &mut Ray, sampler: &mut Sampler) -> bool {
+        *scattered = Ray::new(rec.point, random_unit_vector(sampler));
+        *color_attenuation = self.albedo.value(rec.u, rec.v, rec.point);
+        true
+    }
+
+    fn eval(&self, _ray_in: Ray, rec: &HitRecord, _direction: DVec3) -> Color {
+        self.albedo.value(rec.u, rec.v, rec.point) / (4.0 * PI)
+    }
+
+    fn scattering_pdf(&self, _ray_in: Ray, _rec: &HitRecord, _direction: DVec3) -> f64 {
+        1.0 / (4.0 * PI)
+    }
+
+    fn is_delta(&self) -> bool {
+        false
+    }
+}
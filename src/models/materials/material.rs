@@ -1,36 +1,79 @@
+use std::sync::Arc;
+
+use std::f64::consts::PI;
+
 use glam::{DVec3, DVec3 as Color, DVec3 as Point};
 
-use crate::{models::{objects::global::HitRecord, ray::Ray}, utils::math::{near_zero, random_unit_vector}};
+use crate::{models::{objects::global::HitRecord, ray::Ray, textures::texture::{SolidColor, Texture}}, utils::{math::random_cosine_direction, onb::Onb, sampler::Sampler}};
 
-pub trait Material {
-    fn scatter(&self, ray_in: Ray, rec: &HitRecord, color_attenuation: &mut Color, scattered: &mut Ray) -> bool;
+// `scatter` samples the direction the light comes from, the attenuation being the scattering function
+// divided by the density of that direction. Materials which scatter into spread out directions also
+// evaluate their scattering function and density for any direction, for the camera to sample lights.
+pub trait Material: Send + Sync {
+    fn scatter(&self, ray_in: Ray, rec: &HitRecord, color_attenuation: &mut Color, scattered: &mut Ray, sampler: &mut Sampler) -> bool;
     fn color_emitted(&self, _u:f64, _v:f64, _point: Point) -> Color {
         Color::new(0.0, 0.0, 0.0)
     }
+    // Emitters can be sampled as lights
+    fn is_emissive(&self) -> bool {
+        false
+    }
+    // Scattering function toward `direction`, multiplied by its cosine with the normal for surfaces
+    // (a BSDF), or the phase function for volumes
+    fn eval(&self, _ray_in: Ray, _rec: &HitRecord, _direction: DVec3) -> Color {
+        Color::ZERO
+    }
+    // Density over solid angle of `scatter` picking `direction`
+    fn scattering_pdf(&self, _ray_in: Ray, _rec: &HitRecord, _direction: DVec3) -> f64 {
+        0.0
+    }
+    // Scattering into a single direction (mirrors, glass): no density describes it, so only `scatter` is used.
+    // Materials implementing `eval` and `scattering_pdf` return false.
+    fn is_delta(&self) -> bool {
+        true
+    }
 }
 
 pub struct Lambertian {
-    albedo:Color,
+    albedo: Arc<dyn Texture>,
 }
 
 impl Lambertian {
     pub fn new(color:Color) -> Self {
-        Self{
-            albedo:color,
+        Self::from_texture(Arc::new(SolidColor::new(color)))
+    }
+
+    pub fn from_texture(texture: Arc<dyn Texture>) -> Self {
+        Self { albedo: texture }
+    }
+
+    fn albedo_at(&self, rec: &HitRecord) -> Color {
+        let albedo: Color = self.albedo.value(rec.u, rec.v, rec.point);
+        match rec.vertex_color {
+            Some(vertex_color) => albedo * vertex_color,
+            None => albedo,
         }
     }
 }
 
 impl Material for Lambertian {
-    fn scatter(&self, _ray_in: Ray, rec: &HitRecord, color_attenuation: &mut Color, scattered: &mut Ray) -> bool {
-        let mut scatter_direction: DVec3 = rec.normal + random_unit_vector();
-        
-        if near_zero(scatter_direction) {
-            scatter_direction = rec.normal;
-        }
-
+    fn scatter(&self, _ray_in: Ray, rec: &HitRecord, color_attenuation: &mut Color, scattered: &mut Ray, sampler: &mut Sampler) -> bool {
+        // Cosine weighted around the normal, so the cosine and the density cancel out and the attenuation is the albedo
+        let scatter_direction: DVec3 = Onb::new(rec.normal).transform(random_cosine_direction(sampler));
         *scattered = Ray::new(rec.point, scatter_direction);
-        *color_attenuation =  self.albedo;
+        *color_attenuation = self.albedo_at(rec);
         true
     }
+
+    fn eval(&self, _ray_in: Ray, rec: &HitRecord, direction: DVec3) -> Color {
+        self.albedo_at(rec) / PI * rec.normal.dot(direction.normalize()).max(0.0)
+    }
+
+    fn scattering_pdf(&self, _ray_in: Ray, rec: &HitRecord, direction: DVec3) -> f64 {
+        rec.normal.dot(direction.normalize()).max(0.0) / PI
+    }
+
+    fn is_delta(&self) -> bool {
+        false
+    }
 }
\ No newline at end of file
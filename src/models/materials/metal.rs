@@ -1,23 +1,36 @@
+use std::{f64::consts::PI, sync::Arc};
+
 use glam::{DVec3, DVec3 as Color};
 
 use crate::{
-    models::{objects::global::HitRecord, ray::Ray},
-    utils::math::{random_unit_vector, reflect},
+    models::{
+        objects::global::HitRecord,
+        ray::Ray,
+        textures::texture::{SolidColor, Texture},
+    },
+    utils::{
+        math::{random_unit_vector, reflect},
+        sampler::Sampler,
+    },
 };
 
 use super::material::Material;
 
 pub struct Metal {
-    albedo: Color,
+    albedo: Arc<dyn Texture>,
     fuzziness: f64,
 }
 
 impl Metal {
-    pub fn new(color: Color, mut fuzziness:f64) -> Self {
+    pub fn new(color: Color, fuzziness:f64) -> Self {
+        Self::from_texture(Arc::new(SolidColor::new(color)), fuzziness)
+    }
+
+    pub fn from_texture(texture: Arc<dyn Texture>, mut fuzziness: f64) -> Self {
         if fuzziness > 1.0 {
             fuzziness = 1.0;
         }
-        Self { albedo: color, fuzziness }
+        Self { albedo: texture, fuzziness }
     }
 }
 
@@ -28,11 +41,48 @@ impl Material for Metal {
         rec: &HitRecord,
         color_attenuation: &mut DVec3,
         scattered: &mut Ray,
+        sampler: &mut Sampler,
     ) -> bool {
         let mut reflected: DVec3 = reflect(ray_in.direction, rec.normal);
-        reflected = reflected.normalize() + (self.fuzziness * random_unit_vector());
+        reflected = reflected.normalize() + (self.fuzziness * random_unit_vector(sampler));
         *scattered = Ray::new(rec.point, reflected);
-        *color_attenuation = self.albedo;
-        true
+        *color_attenuation = self.albedo.value(rec.u, rec.v, rec.point);
+        // The fuzz may push the reflection under the surface, it's absorbed there
+        reflected.dot(rec.normal) > 0.0
+    }
+
+    fn eval(&self, ray_in: Ray, rec: &HitRecord, direction: DVec3) -> Color {
+        if direction.dot(rec.normal) <= 0.0 {
+            return Color::ZERO;
+        }
+        self.albedo.value(rec.u, rec.v, rec.point) * self.scattering_pdf(ray_in, rec, direction)
+    }
+
+    // The scattered directions go from the hit toward a uniform point of the sphere of radius `fuzziness`
+    // around the tip of the mirror reflection. Along `direction` the density is the one of the sphere's
+    // surface, 1 / (4 pi f^2), brought to solid angle at each point where the direction crosses it.
+    fn scattering_pdf(&self, ray_in: Ray, rec: &HitRecord, direction: DVec3) -> f64 {
+        if self.fuzziness <= 0.0 || direction.dot(rec.normal) <= 0.0 {
+            return 0.0;
+        }
+        let reflected: DVec3 = reflect(ray_in.direction.normalize(), rec.normal);
+        let direction: DVec3 = direction.normalize();
+        // The points of the sphere at t along the direction
+        let b: f64 = direction.dot(reflected);
+        let discriminant: f64 = b * b - (1.0 - self.fuzziness * self.fuzziness);
+        if discriminant <= 0.0 {
+            return 0.0;
+        }
+        let root: f64 = discriminant.sqrt();
+        [b - root, b + root]
+            .iter()
+            .filter(|&&t| t > 0.0)
+            .map(|t| t * t / (4.0 * PI * self.fuzziness * root))
+            .sum()
+    }
+
+    // Only a mirror without fuzz reflects in a single direction
+    fn is_delta(&self) -> bool {
+        self.fuzziness <= 0.0
     }
 }
@@ -1,4 +1,5 @@
 pub mod material;
 pub mod metal;
 pub mod dielectric;
-pub mod diffuse_light;
\ No newline at end of file
+pub mod diffuse_light;
+pub mod isotropic;
\ No newline at end of file
@@ -1,5 +1,9 @@
 pub mod camera;
+pub mod image;
 pub mod instances;
+pub mod lights;
 pub mod materials;
 pub mod objects;
-pub mod ray;
\ No newline at end of file
+pub mod ray;
+pub mod scene;
+pub mod textures;
\ No newline at end of file
@@ -0,0 +1,161 @@
+use std::ops::Range;
+
+use glam::{DVec3, DVec3 as Point};
+
+use crate::{
+    models::ray::Ray,
+    utils::math::{expand, new_interval},
+};
+
+#[derive(Clone, Debug)]
+pub struct Aabb {
+    pub x: Range<f64>,
+    pub y: Range<f64>,
+    pub z: Range<f64>,
+}
+
+impl Default for Aabb {
+    fn default() -> Self {
+        Self::empty()
+    }
+}
+
+impl Aabb {
+    pub fn new(x: Range<f64>, y: Range<f64>, z: Range<f64>) -> Self {
+        let mut bbox: Aabb = Self { x, y, z };
+        bbox.pad_to_minimums();
+        bbox
+    }
+
+    // A box which contains nothing, the neutral element of `surrounding`
+    pub fn empty() -> Self {
+        Self {
+            x: f64::INFINITY..f64::NEG_INFINITY,
+            y: f64::INFINITY..f64::NEG_INFINITY,
+            z: f64::INFINITY..f64::NEG_INFINITY,
+        }
+    }
+
+    // Treat the two points a and b as extrema for the bounding box, so we don't require a particular min/max coordinate order
+    pub fn from_points(a: Point, b: Point) -> Self {
+        Self::new(
+            a.x.min(b.x)..a.x.max(b.x),
+            a.y.min(b.y)..a.y.max(b.y),
+            a.z.min(b.z)..a.z.max(b.z),
+        )
+    }
+
+    pub fn surrounding(box0: &Aabb, box1: &Aabb) -> Self {
+        Self {
+            x: new_interval(box0.x.clone(), box1.x.clone()),
+            y: new_interval(box0.y.clone(), box1.y.clone()),
+            z: new_interval(box0.z.clone(), box1.z.clone()),
+        }
+    }
+
+    pub fn axis_interval(&self, n: usize) -> &Range<f64> {
+        match n {
+            1 => &self.y,
+            2 => &self.z,
+            _ => &self.x,
+        }
+    }
+
+    pub fn min(&self) -> Point {
+        Point::new(self.x.start, self.y.start, self.z.start)
+    }
+
+    pub fn max(&self) -> Point {
+        Point::new(self.x.end, self.y.end, self.z.end)
+    }
+
+    pub fn corners(&self) -> [Point; 8] {
+        let mut corners: [Point; 8] = [Point::ZERO; 8];
+        for (n, corner) in corners.iter_mut().enumerate() {
+            *corner = Point::new(
+                if n & 1 == 0 { self.x.start } else { self.x.end },
+                if n & 2 == 0 { self.y.start } else { self.y.end },
+                if n & 4 == 0 { self.z.start } else { self.z.end },
+            );
+        }
+        corners
+    }
+
+    pub fn hit(&self, ray: Ray, ray_t: Range<f64>) -> bool {
+        self.hit_interval(ray, ray_t).is_some()
+    }
+
+    // Part of the interval where the ray is inside the box
+    pub fn hit_interval(&self, ray: Ray, mut ray_t: Range<f64>) -> Option<Range<f64>> {
+        for axis in 0..3 {
+            let ax: &Range<f64> = self.axis_interval(axis);
+            let adinv: f64 = 1.0 / ray.direction[axis];
+
+            let t0: f64 = (ax.start - ray.origin[axis]) * adinv;
+            let t1: f64 = (ax.end - ray.origin[axis]) * adinv;
+
+            if t0 < t1 {
+                ray_t.start = ray_t.start.max(t0);
+                ray_t.end = ray_t.end.min(t1);
+            } else {
+                ray_t.start = ray_t.start.max(t1);
+                ray_t.end = ray_t.end.min(t0);
+            }
+
+            if ray_t.end <= ray_t.start {
+                return None;
+            }
+        }
+        Some(ray_t)
+    }
+
+    pub fn centroid(&self) -> Point {
+        0.5 * (self.min() + self.max())
+    }
+
+    pub fn surface_area(&self) -> f64 {
+        let extent: DVec3 = self.max() - self.min();
+        if extent.x < 0.0 || extent.y < 0.0 || extent.z < 0.0 {
+            return 0.0;
+        }
+        2.0 * (extent.x * extent.y + extent.y * extent.z + extent.z * extent.x)
+    }
+
+    // Returns the index of the longest axis of the bounding box
+    pub fn longest_axis(&self) -> usize {
+        let extent: DVec3 = self.max() - self.min();
+        if extent.x > extent.y {
+            if extent.x > extent.z {
+                0
+            } else {
+                2
+            }
+        } else if extent.y > extent.z {
+            1
+        } else {
+            2
+        }
+    }
+
+    pub fn translate(&self, offset: DVec3) -> Self {
+        Self {
+            x: self.x.start + offset.x..self.x.end + offset.x,
+            y: self.y.start + offset.y..self.y.end + offset.y,
+            z: self.z.start + offset.z..self.z.end + offset.z,
+        }
+    }
+
+    // Adjust the AABB so that no side is narrower than some delta, padding if necessary (flat quads would be missed otherwise)
+    fn pad_to_minimums(&mut self) {
+        let delta: f64 = 0.0001;
+        if self.x.end - self.x.start < delta {
+            self.x = expand(self.x.clone(), delta);
+        }
+        if self.y.end - self.y.start < delta {
+            self.y = expand(self.y.clone(), delta);
+        }
+        if self.z.end - self.z.start < delta {
+            self.z = expand(self.z.clone(), delta);
+        }
+    }
+}
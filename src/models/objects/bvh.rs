@@ -0,0 +1,161 @@
+use std::{cmp::Ordering, fmt, ops::Range, sync::Arc};
+
+use crate::models::ray::Ray;
+
+use super::{
+    aabb::Aabb,
+    global::{gather_lights, HitRecord, Hittable, HittableList},
+    medium::MediumSegment,
+};
+
+// Relative costs used by the surface area heuristic, a box test is much cheaper than testing an object
+pub const TRAVERSAL_COST: f64 = 0.125;
+pub const INTERSECTION_COST: f64 = 1.0;
+
+#[derive(Clone, Copy, Debug, Default)]
+pub struct BvhStats {
+    pub node_count: usize,
+    pub leaf_count: usize,
+    pub depth: usize,
+    // Expected cost of tracing a ray through the tree according to the surface area heuristic
+    pub traversal_cost: f64,
+}
+
+impl fmt::Display for BvhStats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} nodes, {} leaves, depth {}, estimated cost {:.3}",
+            self.node_count, self.leaf_count, self.depth, self.traversal_cost
+        )
+    }
+}
+
+pub struct BvhNode {
+    pub left: Arc<dyn Hittable>,
+    pub right: Arc<dyn Hittable>,
+    bbox: Aabb,
+    stats: BvhStats,
+    // Whether there are volumes under the node, the others are skipped when looking for them
+    has_media: bool,
+}
+
+impl BvhNode {
+    pub fn new(list: HittableList) -> Self {
+        let mut objects: Vec<Arc<dyn Hittable>> = list.objects;
+        let end: usize = objects.len();
+        Self::from_objects(&mut objects, 0, end)
+    }
+
+    // Builds a tree over objects[start..end], splitting along the longest axis of the span's bounding box
+    pub fn from_objects(objects: &mut [Arc<dyn Hittable>], start: usize, end: usize) -> Self {
+        let mut bbox: Aabb = Aabb::empty();
+        for object in objects[start..end].iter() {
+            bbox = Aabb::surrounding(&bbox, &object.bounding_box());
+        }
+
+        let axis: usize = bbox.longest_axis();
+        let object_span: usize = end - start;
+
+        let area: f64 = bbox.surface_area();
+        // Objects directly under this node are tested every time the node is entered
+        let object_leaves: BvhStats = BvhStats {
+            node_count: 3,
+            leaf_count: 2,
+            depth: 1,
+            traversal_cost: TRAVERSAL_COST + 2.0 * INTERSECTION_COST,
+        };
+
+        let (left, right, stats): (Arc<dyn Hittable>, Arc<dyn Hittable>, BvhStats) = match object_span {
+            // An empty list still needs two children, the empty list never gets hit
+            0 => {
+                let empty: Arc<dyn Hittable> = Arc::new(HittableList::new());
+                (empty.clone(), empty, object_leaves)
+            }
+            1 => (objects[start].clone(), objects[start].clone(), object_leaves),
+            2 => (objects[start].clone(), objects[start + 1].clone(), object_leaves),
+            _ => {
+                objects[start..end].sort_by(|a, b| box_compare(a, b, axis));
+
+                let mid: usize = start + object_span / 2;
+                let left: BvhNode = Self::from_objects(objects, start, mid);
+                let right: BvhNode = Self::from_objects(objects, mid, end);
+
+                let children_cost: f64 = if area > 0.0 {
+                    (left.stats.traversal_cost * left.bbox.surface_area()
+                        + right.stats.traversal_cost * right.bbox.surface_area())
+                        / area
+                } else {
+                    left.stats.traversal_cost + right.stats.traversal_cost
+                };
+                let stats: BvhStats = BvhStats {
+                    node_count: 1 + left.stats.node_count + right.stats.node_count,
+                    leaf_count: left.stats.leaf_count + right.stats.leaf_count,
+                    depth: 1 + left.stats.depth.max(right.stats.depth),
+                    traversal_cost: TRAVERSAL_COST + children_cost,
+                };
+                (Arc::new(left), Arc::new(right), stats)
+            }
+        };
+
+        let has_media: bool = left.has_media() || right.has_media();
+        Self {
+            left,
+            right,
+            bbox,
+            stats,
+            has_media,
+        }
+    }
+
+    pub fn stats(&self) -> BvhStats {
+        self.stats
+    }
+}
+
+impl Hittable for BvhNode {
+    fn hit(&self, ray: Ray, ray_t: Range<f64>, rec: &mut HitRecord) -> bool {
+        if !self.bbox.hit(ray, ray_t.clone()) {
+            return false;
+        }
+
+        let hit_left: bool = self.left.hit(ray, ray_t.clone(), rec);
+        let right_end: f64 = if hit_left { rec.t } else { ray_t.end };
+        let hit_right: bool = self.right.hit(ray, ray_t.start..right_end, rec);
+
+        hit_left || hit_right
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        self.bbox.clone()
+    }
+
+    fn lights(&self) -> Vec<Arc<dyn Hittable>> {
+        // A single object sits on both sides
+        if Arc::ptr_eq(&self.left, &self.right) {
+            gather_lights([&self.left])
+        } else {
+            gather_lights([&self.left, &self.right])
+        }
+    }
+
+    fn has_media(&self) -> bool {
+        self.has_media
+    }
+
+    fn media<'a>(&'a self, ray: Ray, ray_t: Range<f64>, segments: &mut Vec<MediumSegment<'a>>) {
+        if !self.has_media || !self.bbox.hit(ray, ray_t.clone()) {
+            return;
+        }
+        self.left.media(ray, ray_t.clone(), segments);
+        if !Arc::ptr_eq(&self.left, &self.right) {
+            self.right.media(ray, ray_t, segments);
+        }
+    }
+}
+
+pub fn box_compare(a: &Arc<dyn Hittable>, b: &Arc<dyn Hittable>, axis: usize) -> Ordering {
+    let a_axis_start: f64 = a.bounding_box().axis_interval(axis).start;
+    let b_axis_start: f64 = b.bounding_box().axis_interval(axis).start;
+    a_axis_start.total_cmp(&b_axis_start)
+}
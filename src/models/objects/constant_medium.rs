@@ -0,0 +1,139 @@
+use std::{ops::Range, sync::Arc};
+
+use glam::{DVec3 as Color, DVec3 as Point};
+
+use crate::{
+    models::{
+        materials::{isotropic::Isotropic, material::Material},
+        ray::Ray,
+        textures::texture::Texture,
+    },
+    utils::sampler::Sampler,
+};
+
+use super::{
+    aabb::Aabb,
+    global::{HitRecord, Hittable},
+    medium::{Medium, MediumSegment},
+};
+
+// Volume of constant density filling a boundary object, like smoke or fog.
+// A ray going through it gets scattered with a probability growing with the distance travelled inside,
+// `density` being the probability per unit of distance. The boundary has to be convex, a ray only
+// enters it once and leaves it once.
+pub struct ConstantMedium {
+    pub boundary: Arc<dyn Hittable>,
+    pub phase_function: Arc<dyn Material>,
+    pub density: f64,
+}
+
+impl ConstantMedium {
+    pub fn new(boundary: Arc<dyn Hittable>, density: f64, color: Color) -> Self {
+        Self::with_phase_function(boundary, density, Arc::new(Isotropic::new(color)))
+    }
+
+    pub fn from_texture(boundary: Arc<dyn Hittable>, density: f64, texture: Arc<dyn Texture>) -> Self {
+        Self::with_phase_function(boundary, density, Arc::new(Isotropic::from_texture(texture)))
+    }
+
+    pub fn with_phase_function(boundary: Arc<dyn Hittable>, density: f64, phase_function: Arc<dyn Material>) -> Self {
+        Self {
+            boundary,
+            phase_function,
+            density,
+        }
+    }
+}
+
+impl Medium for ConstantMedium {
+    fn majorant(&self) -> f64 {
+        self.density
+    }
+
+    // The color of the medium is the albedo of its phase function
+    fn coefficients(&self, _point: Point) -> (Color, Color) {
+        (Color::ZERO, Color::splat(self.density))
+    }
+
+    fn phase_function(&self) -> Arc<dyn Material> {
+        self.phase_function.clone()
+    }
+
+    fn transmittance(&self, ray: Ray, ray_t: Range<f64>, _sampler: &mut Sampler) -> Color {
+        Color::splat((-self.density * (ray_t.end - ray_t.start) * ray.direction.length()).exp())
+    }
+}
+
+impl Hittable for ConstantMedium {
+    // The medium has no surface, the camera tracks the rays going through it
+    fn hit(&self, _ray: Ray, _ray_t: Range<f64>, _rec: &mut HitRecord) -> bool {
+        false
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        self.boundary.bounding_box()
+    }
+
+    fn has_media(&self) -> bool {
+        true
+    }
+
+    fn media<'a>(&'a self, ray: Ray, ray_t: Range<f64>, segments: &mut Vec<MediumSegment<'a>>) {
+        // Where the whole line enters and leaves the boundary, then clipped to the interval
+        let mut entry: HitRecord = HitRecord::new();
+        let mut exit: HitRecord = HitRecord::new();
+        if !self.boundary.hit(ray, f64::NEG_INFINITY..f64::INFINITY, &mut entry) {
+            return;
+        }
+        if !self.boundary.hit(ray, entry.t + 0.0001..f64::INFINITY, &mut exit) {
+            return;
+        }
+
+        let range: Range<f64> = entry.t.max(ray_t.start)..exit.t.min(ray_t.end);
+        if range.start < range.end {
+            segments.push(MediumSegment { medium: self, ray, range });
+        }
+    }
+}
+
+// Homogeneous medium filling the whole scene, for atmospheric haze. The camera tracks every ray through it
+// up to the surface it hits, and rays leaving the scene still go through `extent` of it.
+pub struct Haze {
+    pub density: f64,
+    pub phase_function: Arc<dyn Material>,
+    pub extent: f64,
+}
+
+impl Haze {
+    pub fn new(density: f64, color: Color, extent: f64) -> Self {
+        Self {
+            density,
+            phase_function: Arc::new(Isotropic::new(color)),
+            extent,
+        }
+    }
+
+    // The part of the ray inside the haze, from its origin to `t_max` or as far as the haze goes
+    pub fn segment(&self, ray: Ray, t_max: f64) -> MediumSegment<'_> {
+        let end: f64 = if t_max.is_finite() { t_max } else { self.extent / ray.direction.length() };
+        MediumSegment { medium: self, ray, range: 0.0..end }
+    }
+}
+
+impl Medium for Haze {
+    fn majorant(&self) -> f64 {
+        self.density
+    }
+
+    fn coefficients(&self, _point: Point) -> (Color, Color) {
+        (Color::ZERO, Color::splat(self.density))
+    }
+
+    fn phase_function(&self) -> Arc<dyn Material> {
+        self.phase_function.clone()
+    }
+
+    fn transmittance(&self, ray: Ray, ray_t: Range<f64>, _sampler: &mut Sampler) -> Color {
+        Color::splat((-self.density * (ray_t.end - ray_t.start) * ray.direction.length()).exp())
+    }
+}
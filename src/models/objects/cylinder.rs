@@ -1,16 +1,16 @@
-use std::{ops::Range, rc::Rc};
+use std::{f64::consts::PI, ops::Range, sync::Arc};
 
 use glam::{DVec3, DVec3 as Point};
 
 use crate::models::{materials::material::Material, ray::Ray};
 
-use super::global::{HitRecord, Hittable};
+use super::{aabb::Aabb, global::{HitRecord, Hittable}};
 pub struct Cylinder {
     pub origin: Point,
     pub axis: DVec3,
     pub radius: f64,
     pub height: f64,
-    pub mat: Rc<dyn Material>,
+    pub mat: Arc<dyn Material>,
 }
 
 impl Cylinder {
@@ -19,16 +19,24 @@ impl Cylinder {
         axis: DVec3,
         radius: f64,
         height: f64,
-        mat: Rc<dyn Material>,
+        mat: Arc<dyn Material>,
     ) -> Self {
+        // The hit only works with a unit axis, the height gives the length
         Self {
             origin,
-            axis,
+            axis: axis.normalize(),
             radius,
             height,
             mat,
         }
     }
+
+    // Angle of an offset around the axis as a fraction of a turn, from a fixed direction orthogonal to the axis
+    fn turn_fraction(&self, offset: DVec3) -> f64 {
+        let (x_axis, y_axis): (DVec3, DVec3) = self.axis.normalize().any_orthonormal_pair();
+        let angle: f64 = offset.dot(y_axis).atan2(offset.dot(x_axis));
+        (angle + PI) / (2.0 * PI)
+    }
 }
 
 impl Hittable for Cylinder {
@@ -102,9 +110,14 @@ impl Hittable for Cylinder {
                 if (p1 - self.origin).length_squared() <= self.radius.powi(2) {
                     rec.t = t1;
                     rec.point = p1;
+                    // Caps are mapped radially: u is the angle around the axis and v the distance to the center
+                    rec.u = self.turn_fraction(p1 - self.origin);
+                    rec.v = (p1 - self.origin).length() / self.radius;
                     rec.normal = -self.axis;
                     rec.set_face_normal(ray);
                     rec.mat = self.mat.clone();
+                    rec.vertex_color = None;
+                    rec.sampled_light = false;
                     return true;
                 }
             }
@@ -116,9 +129,14 @@ impl Hittable for Cylinder {
                 {
                     rec.t = t2;
                     rec.point = p2;
+                    let top_offset: DVec3 = p2 - (self.origin + self.axis * self.height);
+                    rec.u = self.turn_fraction(top_offset);
+                    rec.v = top_offset.length() / self.radius;
                     rec.normal = self.axis;
                     rec.set_face_normal(ray);
                     rec.mat = self.mat.clone();
+                    rec.vertex_color = None;
+                    rec.sampled_light = false;
                     return true;
                 }
             }
@@ -131,8 +149,21 @@ impl Hittable for Cylinder {
         rec.normal =
             (rec.point - self.origin - self.axis * (rec.point - self.origin).dot(self.axis))
                 .normalize();
+        // The side is unrolled: u is the angle around the axis and v the height from the bottom cap
+        rec.u = self.turn_fraction(rec.normal);
+        rec.v = height / self.height;
         rec.set_face_normal(ray);
         rec.mat = self.mat.clone();
+        rec.vertex_color = None;
+        rec.sampled_light = false;
         true
     }
+
+    fn bounding_box(&self) -> Aabb {
+        // Each cap is a disk, its extent along an axis shrinks as the cylinder axis gets aligned with it
+        let top: Point = self.origin + self.axis * self.height;
+        let axis: DVec3 = self.axis.normalize();
+        let extent: DVec3 = self.radius * (DVec3::ONE - axis * axis).max(DVec3::ZERO).powf(0.5);
+        Aabb::from_points(self.origin.min(top) - extent, self.origin.max(top) + extent)
+    }
 }
@@ -1,17 +1,30 @@
-use std::{ops::Range, rc::Rc};
+use std::{ops::Range, sync::Arc};
 
-use crate::models::{materials::material::{Lambertian, Material}, ray::Ray};
+use crate::{models::{materials::material::{Lambertian, Material}, ray::Ray}, utils::sampler::Sampler};
 use glam::{DVec3, DVec3 as Point, DVec3 as Color};
 
+use super::{aabb::Aabb, medium::MediumSegment};
+
 #[derive(Clone)]
 pub struct HitRecord {
     pub point: Point,
     pub normal: DVec3,
-    pub mat: Rc<dyn Material>,
+    pub mat: Arc<dyn Material>,
     pub t: f64,
     pub front_face: bool,
     pub u: f64,
     pub v: f64,
+    // Interpolated color of the mesh vertices, it tints the albedo of diffuse materials
+    pub vertex_color: Option<Color>,
+    // Hit on an emitter of the light list, its light may already have reached the previous scattering
+    // through a shadow ray
+    pub sampled_light: bool,
+}
+
+impl Default for HitRecord {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl HitRecord {
@@ -19,11 +32,13 @@ impl HitRecord {
         Self {
             point: Point::new(0.0, 0.0, 0.0),
             normal: Point::new(0.0, 0.0, 0.0),
-            mat: Rc::new(Lambertian::new(Color::new(0.0, 0.0, 0.0))),
+            mat: Arc::new(Lambertian::new(Color::new(0.0, 0.0, 0.0))),
             t: 0.0,
             front_face: false,
             u:0.0,
-            v:0.0
+            v:0.0,
+            vertex_color: None,
+            sampled_light: false,
         }
     }
 
@@ -37,25 +52,72 @@ impl HitRecord {
     }
 }
 
-pub trait Hittable {
+pub trait Hittable: Send + Sync {
     fn hit(&self, ray: Ray, ray_t: Range<f64>, rec: &mut HitRecord) -> bool;
+    fn bounding_box(&self) -> Aabb;
+
+    // Emissive shapes the camera can sample directly say so. Seen from `origin`, `random` gives a direction
+    // toward the shape and `pdf_value` the density over solid angle of `random` giving `direction`.
+    fn is_light(&self) -> bool {
+        false
+    }
+    fn pdf_value(&self, _origin: Point, _direction: DVec3) -> f64 {
+        0.0
+    }
+    fn random(&self, _origin: Point, _sampler: &mut Sampler) -> DVec3 {
+        DVec3::X
+    }
+    // Containers give the lights among their objects
+    fn lights(&self) -> Vec<Arc<dyn Hittable>> {
+        Vec::new()
+    }
+    // Volumes give the parts of the ray going through them within the interval, for the camera to track
+    // it along them. `has_media` lets containers skip the ones without any.
+    fn has_media(&self) -> bool {
+        false
+    }
+    fn media<'a>(&'a self, _ray: Ray, _ray_t: Range<f64>, _segments: &mut Vec<MediumSegment<'a>>) {}
+}
+
+// The objects which are lights, and the lights inside the other ones
+pub fn gather_lights<'a>(objects: impl IntoIterator<Item = &'a Arc<dyn Hittable>>) -> Vec<Arc<dyn Hittable>> {
+    let mut lights: Vec<Arc<dyn Hittable>> = Vec::new();
+    for object in objects {
+        if object.is_light() {
+            lights.push(object.clone());
+        } else {
+            lights.extend(object.lights());
+        }
+    }
+    lights
 }
 
+#[derive(Clone)]
 pub struct HittableList {
-    pub objects: Vec<Rc<dyn Hittable>>,
+    pub objects: Vec<Arc<dyn Hittable>>,
+    bbox: Aabb,
+}
+
+impl Default for HittableList {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl HittableList {
     pub fn new() -> Self {
         Self {
             objects: Vec::new(),
+            bbox: Aabb::empty(),
         }
     }
-    pub fn add(&mut self, object: Rc<dyn Hittable>) {
+    pub fn add(&mut self, object: Arc<dyn Hittable>) {
+        self.bbox = Aabb::surrounding(&self.bbox, &object.bounding_box());
         self.objects.push(object.clone());
     }
     pub fn clear(&mut self) {
         self.objects.clear();
+        self.bbox = Aabb::empty();
     }
 }
 
@@ -68,10 +130,28 @@ impl Hittable for HittableList {
         for object in self.objects.iter() {
             if object.hit(ray, ray_t.start..closest_so_far, &mut temp_rec) {
                 hit_anything = true;
-                closest_so_far = temp_rec.t.clone();
+                closest_so_far = temp_rec.t;
                 *rec = temp_rec.clone()
             }
         }
         hit_anything
     }
+
+    fn bounding_box(&self) -> Aabb {
+        self.bbox.clone()
+    }
+
+    fn lights(&self) -> Vec<Arc<dyn Hittable>> {
+        gather_lights(&self.objects)
+    }
+
+    fn has_media(&self) -> bool {
+        self.objects.iter().any(|object| object.has_media())
+    }
+
+    fn media<'a>(&'a self, ray: Ray, ray_t: Range<f64>, segments: &mut Vec<MediumSegment<'a>>) {
+        for object in self.objects.iter() {
+            object.media(ray, ray_t.clone(), segments);
+        }
+    }
 }
@@ -0,0 +1,201 @@
+use std::{ops::Range, sync::Arc};
+
+use glam::{DVec3, DVec3 as Color, DVec3 as Point};
+
+use crate::{
+    models::{
+        materials::{isotropic::Isotropic, material::Material},
+        ray::Ray,
+        textures::perlin::Perlin,
+    },
+    utils::{math::random_float, sampler::Sampler},
+};
+
+use super::{
+    aabb::Aabb,
+    global::{HitRecord, Hittable},
+    medium::{Medium, MediumSegment},
+};
+
+// Densities sampled on a regular grid over the unit cube, each value sitting at the center of its cell
+pub struct DensityGrid {
+    pub resolution: [usize; 3],
+    // x varies the fastest, then y, then z
+    pub values: Vec<f64>,
+    max: f64,
+}
+
+impl DensityGrid {
+    pub fn new(resolution: [usize; 3], values: Vec<f64>) -> Self {
+        assert_eq!(
+            values.len(),
+            resolution.iter().product::<usize>(),
+            "the grid needs one value per cell"
+        );
+        // A density can't be negative
+        let values: Vec<f64> = values.into_iter().map(|value| value.max(0.0)).collect();
+        let max: f64 = values.iter().copied().fold(0.0, f64::max);
+        Self { resolution, values, max }
+    }
+
+    // Grid filled by a function of the center of each cell, in 0..1 on every axis
+    pub fn from_fn(resolution: [usize; 3], density: impl Fn(Point) -> f64) -> Self {
+        let [nx, ny, nz] = resolution;
+        let mut values: Vec<f64> = Vec::with_capacity(nx * ny * nz);
+        for k in 0..nz {
+            for j in 0..ny {
+                for i in 0..nx {
+                    let center: Point = (DVec3::new(i as f64, j as f64, k as f64) + 0.5)
+                        / DVec3::new(nx as f64, ny as f64, nz as f64);
+                    values.push(density(center));
+                }
+            }
+        }
+        Self::new(resolution, values)
+    }
+
+    // Cloud like puff: turbulence fading out toward the sides of the cube so the volume has no hard edges,
+    // scaled so its densest point is 1.0. `scale` is the frequency of the noise.
+    pub fn from_noise(resolution: [usize; 3], scale: f64, sampler: &mut Sampler) -> Self {
+        let perlin: Perlin = Perlin::new(sampler);
+        let grid: DensityGrid = Self::from_fn(resolution, |point| {
+            let falloff: f64 = (1.0 - 2.0 * (point - 0.5).length()).max(0.0);
+            falloff * perlin.turbulence(scale * point, 7)
+        });
+        if grid.max > 0.0 {
+            let max: f64 = grid.max;
+            Self::new(resolution, grid.values.iter().map(|value| value / max).collect())
+        } else {
+            grid
+        }
+    }
+
+    pub fn max(&self) -> f64 {
+        self.max
+    }
+
+    // Trilinear interpolation between the cell centers, `point` in 0..1 on every axis
+    pub fn density(&self, point: Point) -> f64 {
+        if self.values.is_empty() {
+            return 0.0;
+        }
+        let [nx, ny, nz] = self.resolution;
+        let cell: DVec3 = point * DVec3::new(nx as f64, ny as f64, nz as f64) - 0.5;
+        let lower: DVec3 = cell.floor();
+        let fraction: DVec3 = cell - lower;
+
+        let value = |i: f64, j: f64, k: f64| -> f64 {
+            let i: usize = i.clamp(0.0, (nx - 1) as f64) as usize;
+            let j: usize = j.clamp(0.0, (ny - 1) as f64) as usize;
+            let k: usize = k.clamp(0.0, (nz - 1) as f64) as usize;
+            self.values[(k * ny + j) * nx + i]
+        };
+
+        let mut accumulated: f64 = 0.0;
+        for (dk, wk) in [(0.0, 1.0 - fraction.z), (1.0, fraction.z)] {
+            for (dj, wj) in [(0.0, 1.0 - fraction.y), (1.0, fraction.y)] {
+                for (di, wi) in [(0.0, 1.0 - fraction.x), (1.0, fraction.x)] {
+                    accumulated += wi * wj * wk * value(lower.x + di, lower.y + dj, lower.z + dk);
+                }
+            }
+        }
+        accumulated
+    }
+}
+
+// Volume of varying density, like a cloud or an explosion. The grid is stretched over the box from `min`
+// to `max`, and the coefficients are the chances per unit of distance of each event where the grid is 1.0,
+// one per color channel.
+//
+// The camera tracks rays through it with delta tracking against the densest point of the grid, and shadow
+// rays get its transmittance with ratio tracking. Both are unbiased whatever the density looks like.
+pub struct GridVolume {
+    pub grid: Arc<DensityGrid>,
+    pub absorption: Color,
+    pub scattering: Color,
+    // Radiance emitted where the medium absorbs light, for fire and explosions
+    pub emission: Color,
+    // The color comes from the scattering coefficients, the phase function scatters everything
+    pub phase_function: Arc<dyn Material>,
+    bbox: Aabb,
+}
+
+impl GridVolume {
+    pub fn new(grid: Arc<DensityGrid>, min: Point, max: Point, absorption: Color, scattering: Color) -> Self {
+        Self {
+            grid,
+            absorption,
+            scattering,
+            emission: Color::ZERO,
+            phase_function: Arc::new(Isotropic::new(Color::ONE)),
+            bbox: Aabb::from_points(min, max),
+        }
+    }
+
+    fn density_at(&self, point: Point) -> f64 {
+        let min: Point = self.bbox.min();
+        self.grid.density((point - min) / (self.bbox.max() - min))
+    }
+}
+
+impl Medium for GridVolume {
+    // Density of the thickest channel at the densest point
+    fn majorant(&self) -> f64 {
+        self.grid.max() * (self.absorption + self.scattering).max_element()
+    }
+
+    fn coefficients(&self, point: Point) -> (Color, Color) {
+        let density: f64 = self.density_at(point);
+        (self.absorption * density, self.scattering * density)
+    }
+
+    fn emission(&self) -> Color {
+        self.emission
+    }
+
+    fn phase_function(&self) -> Arc<dyn Material> {
+        self.phase_function.clone()
+    }
+
+    // Ratio tracking: every tentative collision multiplies the transmittance by the chance of it being
+    // a null one
+    fn transmittance(&self, ray: Ray, ray_t: Range<f64>, sampler: &mut Sampler) -> Color {
+        let majorant: f64 = self.majorant();
+        let Some(inside) = self.bbox.hit_interval(ray, ray_t).filter(|_| majorant > 0.0) else {
+            return Color::ONE;
+        };
+
+        let step: f64 = 1.0 / (majorant * ray.direction.length());
+        let mut transmittance: Color = Color::ONE;
+        let mut t: f64 = inside.start;
+        loop {
+            t -= (1.0 - random_float(sampler)).ln() * step;
+            if t >= inside.end {
+                return transmittance;
+            }
+            let extinction: Color = (self.absorption + self.scattering) * self.density_at(ray.at(t));
+            transmittance *= Color::ONE - extinction / majorant;
+        }
+    }
+}
+
+impl Hittable for GridVolume {
+    // The volume has no surface, the camera tracks the rays going through it
+    fn hit(&self, _ray: Ray, _ray_t: Range<f64>, _rec: &mut HitRecord) -> bool {
+        false
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        self.bbox.clone()
+    }
+
+    fn has_media(&self) -> bool {
+        true
+    }
+
+    fn media<'a>(&'a self, ray: Ray, ray_t: Range<f64>, segments: &mut Vec<MediumSegment<'a>>) {
+        if let Some(range) = self.bbox.hit_interval(ray, ray_t) {
+            segments.push(MediumSegment { medium: self, ray, range });
+        }
+    }
+}
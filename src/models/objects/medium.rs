@@ -0,0 +1,157 @@
+use std::{ops::Range, sync::Arc};
+
+use glam::{DVec3, DVec3 as Color, DVec3 as Point};
+
+use crate::{
+    models::{materials::material::Material, ray::Ray},
+    utils::{math::random_float, sampler::Sampler},
+};
+
+use super::global::HitRecord;
+
+// Participating medium, like smoke, fog or a cloud. Media aren't surfaces the rays hit: the world gives the
+// parts of a ray going through them, and the camera tracks the ray along those with the sampler of the path.
+pub trait Medium: Send + Sync {
+    // Largest extinction (absorption plus scattering) of any channel anywhere in the medium, per unit of
+    // distance in its own space: no collision can be more likely than that
+    fn majorant(&self) -> f64;
+    // Absorption and scattering per unit of distance at a point of its own space
+    fn coefficients(&self, point: Point) -> (Color, Color);
+    // Radiance given off where the medium absorbs light
+    fn emission(&self) -> Color {
+        Color::ZERO
+    }
+    // Material of the scattering events
+    fn phase_function(&self) -> Arc<dyn Material>;
+    // Fraction of the light going through the medium along the ray over the interval
+    fn transmittance(&self, ray: Ray, ray_t: Range<f64>, sampler: &mut Sampler) -> Color;
+}
+
+// Part of a ray going through a medium. `ray` is the ray in the medium's own space, instances move it but
+// keep the same t along it.
+pub struct MediumSegment<'a> {
+    pub medium: &'a dyn Medium,
+    pub ray: Ray,
+    pub range: Range<f64>,
+}
+
+// What happened to a ray going through the media
+pub enum MediumEvent {
+    // Made it to the end of the segments, carrying the weight
+    Passed(Color),
+    // The path ends there, with the weighted emission of the media
+    Absorbed(Color),
+    // Scattered at `t` by the phase function, the weight goes on with the path
+    Scattered { t: f64, weight: Color, phase_function: Arc<dyn Material> },
+}
+
+// Delta tracking through the segments: tentative collisions are drawn with the sum of the majorants of the
+// media covering each part of the ray, and each one is an absorption, a scattering by one of the media, or
+// a null collision letting the ray go on. Each event is picked in proportion to what it brings to the
+// weighted path, the weight then makes up for the channels where that choice doesn't fit (spectral
+// tracking), so colored media stay unbiased.
+pub fn track(segments: &[MediumSegment], sampler: &mut Sampler) -> MediumEvent {
+    let mut weight: Color = Color::ONE;
+    if segments.is_empty() {
+        return MediumEvent::Passed(weight);
+    }
+
+    // The ray is cut where media start or end, the majorant is constant in between
+    let mut bounds: Vec<f64> = segments.iter().flat_map(|segment| [segment.range.start, segment.range.end]).collect();
+    bounds.sort_by(f64::total_cmp);
+    bounds.dedup();
+
+    // Absorption and scattering of each medium covering the current part, at the collision
+    let mut coefficients: Vec<(Color, Color)> = Vec::with_capacity(segments.len());
+    for piece in bounds.windows(2) {
+        let (start, end): (f64, f64) = (piece[0], piece[1]);
+        let covering: Vec<&MediumSegment> = segments
+            .iter()
+            .filter(|segment| segment.range.start <= start && segment.range.end >= end)
+            .collect();
+        // Coefficients are per unit of distance, the tracking goes along t
+        let majorant: f64 = covering
+            .iter()
+            .map(|segment| segment.medium.majorant() * segment.ray.direction.length())
+            .sum();
+        if majorant <= 0.0 {
+            continue;
+        }
+
+        let mut t: f64 = start;
+        loop {
+            t -= (1.0 - random_float(sampler)).ln() / majorant;
+            if t >= end {
+                break;
+            }
+
+            coefficients.clear();
+            let mut absorption: Color = Color::ZERO;
+            let mut emission: Color = Color::ZERO;
+            let mut extinction: Color = Color::ZERO;
+            for segment in &covering {
+                let length: f64 = segment.ray.direction.length();
+                let (segment_absorption, segment_scattering): (Color, Color) =
+                    segment.medium.coefficients(segment.ray.at(t));
+                let (segment_absorption, segment_scattering): (Color, Color) =
+                    (segment_absorption * length, segment_scattering * length);
+                absorption += segment_absorption;
+                emission += segment_absorption * segment.medium.emission();
+                extinction += segment_absorption + segment_scattering;
+                coefficients.push((segment_absorption, segment_scattering));
+            }
+            let null: Color = (Color::splat(majorant) - extinction).max(Color::ZERO);
+
+            let absorption_chance: f64 = (weight * absorption).element_sum();
+            let scattering_chances: f64 = coefficients.iter().map(|(_, scattering)| (weight * *scattering).element_sum()).sum();
+            let null_chance: f64 = (weight * null).element_sum();
+            let total: f64 = absorption_chance + scattering_chances + null_chance;
+            if total <= 0.0 {
+                return MediumEvent::Absorbed(Color::ZERO);
+            }
+            let reweight = |sigma: Color, chance: f64| sigma * total / (majorant * chance);
+
+            let mut choice: f64 = random_float(sampler) * total;
+            if choice < absorption_chance {
+                // Only the emission part of the absorption matters, it's the light the path brings back
+                let emitted: Color = weight * reweight(Color::ONE, absorption_chance) * emission;
+                return MediumEvent::Absorbed(emitted);
+            }
+            choice -= absorption_chance;
+            for (segment, (_, scattering)) in covering.iter().zip(&coefficients) {
+                let chance: f64 = (weight * *scattering).element_sum();
+                if choice < chance {
+                    return MediumEvent::Scattered {
+                        t,
+                        weight: weight * reweight(*scattering, chance),
+                        phase_function: segment.medium.phase_function(),
+                    };
+                }
+                choice -= chance;
+            }
+            weight *= reweight(null, null_chance);
+        }
+    }
+    MediumEvent::Passed(weight)
+}
+
+// Fraction of the light going through all the segments, the extinctions of overlapping media add up so
+// their transmittances multiply
+pub fn transmittance(segments: &[MediumSegment], sampler: &mut Sampler) -> Color {
+    segments.iter().fold(Color::ONE, |transmittance, segment| {
+        transmittance * segment.medium.transmittance(segment.ray, segment.range.clone(), sampler)
+    })
+}
+
+// Scattering happens inside the volume, there is no surface: the normal and the side are arbitrary
+pub(crate) fn set_medium_record(rec: &mut HitRecord, ray: Ray, t: f64, phase_function: Arc<dyn Material>) {
+    rec.t = t;
+    rec.point = ray.at(t);
+    rec.normal = DVec3::new(1.0, 0.0, 0.0);
+    rec.front_face = true;
+    rec.mat = phase_function;
+    rec.u = 0.0;
+    rec.v = 0.0;
+    rec.vertex_color = None;
+    rec.sampled_light = false;
+}
@@ -0,0 +1,138 @@
+use std::{ops::Range, sync::Arc};
+
+use glam::{DVec2, DVec3, DVec3 as Color, DVec3 as Point};
+
+use crate::models::{materials::material::Material, ray::Ray};
+
+use super::{
+    aabb::Aabb,
+    bvh::BvhStats,
+    global::{HitRecord, Hittable},
+    sah_bvh::{build_nodes, nodes_stats, traverse, SahNode},
+    triangle::{intersect_triangle, set_triangle_record, TriangleHit},
+};
+
+// Triangles per leaf of the mesh's BVH
+const MESH_LEAF_SIZE: usize = 4;
+
+// Indexed triangle mesh: every vertex attribute is stored once and the triangles reference them by index.
+// `normals`, `uvs` and `colors` are either empty or have one entry per position.
+//
+// The mesh builds its own BVH over the triangles and is hit as a single object, so a big model can be
+// shared in an Arc and placed many times with Translate and Rotate without being copied.
+pub struct Mesh {
+    pub positions: Vec<Point>,
+    pub normals: Vec<DVec3>,
+    pub uvs: Vec<DVec2>,
+    // Linear colors multiplying the albedo of the diffuse materials
+    pub colors: Vec<Color>,
+    // The triangles are reordered by the BVH build, so every leaf covers a contiguous range of them
+    pub indices: Vec<[u32; 3]>,
+    // Index in `materials` of each triangle
+    pub face_materials: Vec<u32>,
+    pub materials: Vec<Arc<dyn Material>>,
+    nodes: Vec<SahNode>,
+    stats: BvhStats,
+}
+
+impl Mesh {
+    pub fn new(
+        positions: Vec<Point>,
+        normals: Vec<DVec3>,
+        uvs: Vec<DVec2>,
+        colors: Vec<Color>,
+        indices: Vec<[u32; 3]>,
+        face_materials: Vec<u32>,
+        materials: Vec<Arc<dyn Material>>,
+    ) -> Self {
+        let boxes: Vec<Aabb> = indices
+            .iter()
+            .map(|vertices| {
+                let [a, b, c] = vertices.map(|index| positions[index as usize]);
+                Aabb::from_points(a.min(b).min(c), a.max(b).max(c))
+            })
+            .collect();
+        let (nodes, order): (Vec<SahNode>, Vec<usize>) = build_nodes(&boxes, MESH_LEAF_SIZE);
+
+        let stats: BvhStats = nodes_stats(&nodes);
+        Self {
+            positions,
+            normals,
+            uvs,
+            colors,
+            indices: order.iter().map(|&face| indices[face]).collect(),
+            face_materials: order.iter().map(|&face| face_materials[face]).collect(),
+            materials,
+            nodes,
+            stats,
+        }
+    }
+
+    pub fn triangle_count(&self) -> usize {
+        self.indices.len()
+    }
+
+    pub fn stats(&self) -> BvhStats {
+        self.stats
+    }
+
+    fn vertices(&self, face: usize) -> [usize; 3] {
+        self.indices[face].map(|index| index as usize)
+    }
+}
+
+impl Hittable for Mesh {
+    fn hit(&self, ray: Ray, ray_t: Range<f64>, rec: &mut HitRecord) -> bool {
+        // Only the distances are computed while walking the tree, the record is filled once for the closest triangle
+        let mut closest: Option<(usize, TriangleHit)> = None;
+        traverse(&self.nodes, ray, ray_t, |faces, mut interval| {
+            let mut closest_in_leaf: Option<f64> = None;
+            for face in faces {
+                let [a, b, c] = self.vertices(face).map(|index| self.positions[index]);
+                if let Some(hit) = intersect_triangle(a, b, c, ray, &interval) {
+                    interval.end = hit.t;
+                    closest_in_leaf = Some(hit.t);
+                    closest = Some((face, hit));
+                }
+            }
+            closest_in_leaf
+        });
+
+        let Some((face, hit)) = closest else {
+            return false;
+        };
+
+        let [a, b, c] = self.vertices(face);
+        let normal: DVec3 = (self.positions[b] - self.positions[a])
+            .cross(self.positions[c] - self.positions[a])
+            .normalize();
+        // A zero normal marks a vertex without one, the whole triangle is then flat shaded
+        let normals: Option<[DVec3; 3]> = if self.normals.is_empty() {
+            None
+        } else {
+            Some([self.normals[a], self.normals[b], self.normals[c]])
+                .filter(|normals| normals.iter().all(|normal| *normal != DVec3::ZERO))
+        };
+        let uvs: Option<[DVec2; 3]> = if self.uvs.is_empty() {
+            None
+        } else {
+            Some([self.uvs[a], self.uvs[b], self.uvs[c]])
+        };
+        let colors: Option<[Color; 3]> = if self.colors.is_empty() {
+            None
+        } else {
+            Some([self.colors[a], self.colors[b], self.colors[c]])
+        };
+
+        set_triangle_record(rec, ray, hit, normal, normals, uvs, colors);
+        rec.mat = self.materials[self.face_materials[face] as usize].clone();
+        true
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        match self.nodes.first() {
+            Some(node) => node.bbox().clone(),
+            None => Aabb::empty(),
+        }
+    }
+}
@@ -1,4 +1,12 @@
 pub mod sphere;
 pub mod quad;
 pub mod global;
-pub mod cylinder;
\ No newline at end of file
+pub mod cylinder;
+pub mod aabb;
+pub mod bvh;
+pub mod sah_bvh;
+pub mod triangle;
+pub mod mesh;
+pub mod medium;
+pub mod constant_medium;
+pub mod grid_volume;
\ No newline at end of file
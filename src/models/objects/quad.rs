@@ -1,28 +1,40 @@
-use std::{ops::Range, rc::Rc};
+use std::{ops::Range, sync::Arc};
 
 use glam::{DVec3, DVec3 as Point};
 
-use crate::models::{materials::material::Material, ray::Ray};
+use crate::{
+    models::{materials::material::Material, ray::Ray},
+    utils::{math::random_float, sampler::Sampler},
+};
 
-use super::global::{HitRecord, Hittable, HittableList};
+use super::{
+    aabb::Aabb,
+    global::{HitRecord, Hittable, HittableList},
+};
 
 pub struct Quad {
     pub q: Point,
     pub u: DVec3,
     pub v: DVec3,
     pub w: DVec3,
-    pub mat: Rc<dyn Material>,
+    pub mat: Arc<dyn Material>,
     pub normal: DVec3,
     pub d: f64,
+    bbox: Aabb,
 }
 
 impl Quad {
-    pub fn new(q: Point, u: DVec3, v: DVec3, mat: Rc<dyn Material>) -> Self {
+    pub fn new(q: Point, u: DVec3, v: DVec3, mat: Arc<dyn Material>) -> Self {
         let n: DVec3 = u.cross(v);
         let normal: DVec3 = n.normalize();
         let d: f64 = normal.dot(q);
         let w = n / n.length_squared();
 
+        // Compute the bounding box of all four vertices
+        let bbox_diagonal1: Aabb = Aabb::from_points(q, q + u + v);
+        let bbox_diagonal2: Aabb = Aabb::from_points(q + u, q + v);
+        let bbox: Aabb = Aabb::surrounding(&bbox_diagonal1, &bbox_diagonal2);
+
         Self {
             q,
             u,
@@ -31,6 +43,7 @@ impl Quad {
             mat,
             d,
             normal,
+            bbox,
         }
     }
 }
@@ -60,10 +73,36 @@ impl Hittable for Quad {
         rec.t = t;
         rec.point = intersection;
         rec.mat = self.mat.clone();
+        rec.vertex_color = None;
+        rec.sampled_light = self.is_light();
         rec.normal = self.normal;
         rec.set_face_normal(ray);
         true
     }
+
+    fn bounding_box(&self) -> Aabb {
+        self.bbox.clone()
+    }
+
+    fn is_light(&self) -> bool {
+        self.mat.is_emissive()
+    }
+
+    // Uniform over the area, which gets smaller with the distance and the slant seen from the origin
+    fn pdf_value(&self, origin: Point, direction: DVec3) -> f64 {
+        let mut rec: HitRecord = HitRecord::new();
+        if !self.hit(Ray::new(origin, direction), 0.001..f64::INFINITY, &mut rec) {
+            return 0.0;
+        }
+        let distance_squared: f64 = rec.t * rec.t * direction.length_squared();
+        let cosine: f64 = (direction.dot(rec.normal) / direction.length()).abs();
+        distance_squared / (cosine * self.u.cross(self.v).length())
+    }
+
+    fn random(&self, origin: Point, sampler: &mut Sampler) -> DVec3 {
+        let point: Point = self.q + random_float(sampler) * self.u + random_float(sampler) * self.v;
+        point - origin
+    }
 }
 
 fn is_interior(alpha: f64, beta: f64, rec: &mut HitRecord) -> bool {
@@ -76,7 +115,7 @@ fn is_interior(alpha: f64, beta: f64, rec: &mut HitRecord) -> bool {
     true
 }
 
-pub fn box_shape(a: Point, b: Point, mat: Rc<dyn Material>) -> Rc<dyn Hittable> {
+pub fn box_shape(a: Point, b: Point, mat: Arc<dyn Material>) -> Arc<dyn Hittable> {
     let mut sides: HittableList = HittableList::new();
 
     let min: Point = Point::new(a.x.min(b.x), a.y.min(b.y), a.z.min(b.z));
@@ -86,42 +125,42 @@ pub fn box_shape(a: Point, b: Point, mat: Rc<dyn Material>) -> Rc<dyn Hittable>
     let dy: DVec3 = DVec3::new(0.0, max.y - min.y, 0.0);
     let dz: DVec3 = DVec3::new(0.0, 0.0, max.z - min.z);
 
-    sides.add(Rc::new(Quad::new(
+    sides.add(Arc::new(Quad::new(
         Point::new(min.x, min.y, max.z),
         dx,
         dy,
         mat.clone(),
     ))); // front
-    sides.add(Rc::new(Quad::new(
+    sides.add(Arc::new(Quad::new(
         Point::new(max.x, min.y, max.z),
         -dz,
         dy,
         mat.clone(),
     ))); // right
-    sides.add(Rc::new(Quad::new(
+    sides.add(Arc::new(Quad::new(
         Point::new(max.x, min.y, min.z),
         -dx,
         dy,
         mat.clone(),
     ))); // back
-    sides.add(Rc::new(Quad::new(
+    sides.add(Arc::new(Quad::new(
         Point::new(min.x, min.y, min.z),
         dz,
         dy,
         mat.clone(),
     ))); // left
-    sides.add(Rc::new(Quad::new(
+    sides.add(Arc::new(Quad::new(
         Point::new(min.x, max.y, max.z),
         dx,
         -dz,
         mat.clone(),
     ))); // top
-    sides.add(Rc::new(Quad::new(
+    sides.add(Arc::new(Quad::new(
         Point::new(min.x, min.y, min.z),
         dx,
         dz,
         mat.clone(),
     ))); // bottom
 
-    Rc::new(sides)
+    Arc::new(sides)
 }
@@ -0,0 +1,366 @@
+use std::{ops::Range, sync::Arc};
+
+use glam::DVec3 as Point;
+
+use crate::models::ray::Ray;
+
+use super::{
+    aabb::Aabb,
+    bvh::{BvhStats, INTERSECTION_COST, TRAVERSAL_COST},
+    global::{gather_lights, HitRecord, Hittable, HittableList},
+    medium::MediumSegment,
+};
+
+// Number of buckets the centroids are binned into when looking for the cheapest split plane
+const SAH_BINS: usize = 12;
+
+pub(crate) enum SahNode {
+    Interior {
+        bbox: Aabb,
+        axis: usize,
+        second_child: usize,
+    },
+    Leaf {
+        bbox: Aabb,
+        first_object: usize,
+        object_count: usize,
+    },
+}
+
+impl SahNode {
+    pub(crate) fn bbox(&self) -> &Aabb {
+        match self {
+            SahNode::Interior { bbox, .. } | SahNode::Leaf { bbox, .. } => bbox,
+        }
+    }
+}
+
+#[derive(Clone)]
+struct BinnedObject {
+    index: usize,
+    bbox: Aabb,
+    centroid: Point,
+}
+
+#[derive(Clone)]
+struct Bin {
+    bbox: Aabb,
+    count: usize,
+}
+
+// Bounding volume hierarchy built with the binned surface area heuristic.
+// Nodes are stored flattened in depth-first order: the first child of an interior node directly follows it.
+pub struct SahBvh {
+    nodes: Vec<SahNode>,
+    objects: Vec<Arc<dyn Hittable>>,
+    stats: BvhStats,
+    // The few objects holding volumes, asked directly instead of going down the tree
+    media: Vec<Arc<dyn Hittable>>,
+}
+
+impl SahBvh {
+    pub fn new(list: HittableList, max_leaf_size: usize) -> Self {
+        let boxes: Vec<Aabb> = list.objects.iter().map(|object| object.bounding_box()).collect();
+        let (nodes, order): (Vec<SahNode>, Vec<usize>) = build_nodes(&boxes, max_leaf_size);
+
+        // Leaves reference contiguous ranges, so reorder the objects the way the builder partitioned them
+        let objects: Vec<Arc<dyn Hittable>> = order.iter().map(|&index| list.objects[index].clone()).collect();
+
+        let stats: BvhStats = nodes_stats(&nodes);
+        let media: Vec<Arc<dyn Hittable>> = objects.iter().filter(|object| object.has_media()).cloned().collect();
+        Self {
+            nodes,
+            objects,
+            stats,
+            media,
+        }
+    }
+
+    pub fn stats(&self) -> BvhStats {
+        self.stats
+    }
+}
+
+// Builds the nodes over a list of bounding boxes, leaves cover contiguous ranges of the returned order
+// (the indexes of the boxes in the order the builder partitioned them).
+pub(crate) fn build_nodes(boxes: &[Aabb], max_leaf_size: usize) -> (Vec<SahNode>, Vec<usize>) {
+    let max_leaf_size: usize = max_leaf_size.max(1);
+    let mut binned: Vec<BinnedObject> = boxes
+        .iter()
+        .enumerate()
+        .map(|(index, bbox)| BinnedObject {
+            index,
+            centroid: bbox.centroid(),
+            bbox: bbox.clone(),
+        })
+        .collect();
+
+    let mut nodes: Vec<SahNode> = Vec::new();
+    if !binned.is_empty() {
+        build(&mut binned, 0, max_leaf_size, &mut nodes);
+    }
+    (nodes, binned.iter().map(|object| object.index).collect())
+}
+
+pub(crate) fn nodes_stats(nodes: &[SahNode]) -> BvhStats {
+    if nodes.is_empty() {
+        BvhStats::default()
+    } else {
+        node_stats(nodes, 0)
+    }
+}
+
+fn node_stats(nodes: &[SahNode], index: usize) -> BvhStats {
+    match &nodes[index] {
+        SahNode::Leaf { object_count, .. } => BvhStats {
+            node_count: 1,
+            leaf_count: 1,
+            depth: 0,
+            traversal_cost: INTERSECTION_COST * *object_count as f64,
+        },
+        SahNode::Interior {
+            bbox, second_child, ..
+        } => {
+            let left: BvhStats = node_stats(nodes, index + 1);
+            let right: BvhStats = node_stats(nodes, *second_child);
+            let area: f64 = bbox.surface_area();
+            let children_cost: f64 = if area > 0.0 {
+                (left.traversal_cost * nodes[index + 1].bbox().surface_area()
+                    + right.traversal_cost * nodes[*second_child].bbox().surface_area())
+                    / area
+            } else {
+                left.traversal_cost + right.traversal_cost
+            };
+            BvhStats {
+                node_count: 1 + left.node_count + right.node_count,
+                leaf_count: left.leaf_count + right.leaf_count,
+                depth: 1 + left.depth.max(right.depth),
+                traversal_cost: TRAVERSAL_COST + children_cost,
+            }
+        }
+    }
+}
+
+// Recursively builds the nodes over the objects slice, `offset` is the position of the slice in the whole list
+fn build(objects: &mut [BinnedObject], offset: usize, max_leaf_size: usize, nodes: &mut Vec<SahNode>) {
+    let mut bbox: Aabb = Aabb::empty();
+    let mut centroid_min: Point = Point::splat(f64::INFINITY);
+    let mut centroid_max: Point = Point::splat(f64::NEG_INFINITY);
+    for object in objects.iter() {
+        bbox = Aabb::surrounding(&bbox, &object.bbox);
+        centroid_min = centroid_min.min(object.centroid);
+        centroid_max = centroid_max.max(object.centroid);
+    }
+
+    let count: usize = objects.len();
+    let leaf = |bbox: Aabb| SahNode::Leaf {
+        bbox,
+        first_object: offset,
+        object_count: count,
+    };
+
+    if count == 1 {
+        nodes.push(leaf(bbox));
+        return;
+    }
+
+    let centroid_bounds: Aabb = Aabb::from_points(centroid_min, centroid_max);
+    let axis: usize = centroid_bounds.longest_axis();
+    let axis_min: f64 = centroid_min[axis];
+    let axis_extent: f64 = centroid_max[axis] - axis_min;
+
+    // Every centroid sits on the same spot, no plane can separate them
+    if axis_extent <= 0.0 {
+        if count <= max_leaf_size {
+            nodes.push(leaf(bbox));
+        } else {
+            split(objects, offset, count / 2, axis, bbox, max_leaf_size, nodes);
+        }
+        return;
+    }
+
+    let bin_of = |centroid: Point| -> usize {
+        let b: usize = (SAH_BINS as f64 * (centroid[axis] - axis_min) / axis_extent) as usize;
+        b.min(SAH_BINS - 1)
+    };
+
+    let mut bins: Vec<Bin> = vec![
+        Bin {
+            bbox: Aabb::empty(),
+            count: 0,
+        };
+        SAH_BINS
+    ];
+    for object in objects.iter() {
+        let bin: &mut Bin = &mut bins[bin_of(object.centroid)];
+        bin.count += 1;
+        bin.bbox = Aabb::surrounding(&bin.bbox, &object.bbox);
+    }
+
+    // Sweep from the right to get the area of every right side, then from the left to evaluate each plane
+    let mut right_area: Vec<f64> = vec![0.0; SAH_BINS];
+    let mut right_count: Vec<usize> = vec![0; SAH_BINS];
+    let mut right_box: Aabb = Aabb::empty();
+    let mut right_total: usize = 0;
+    for b in (1..SAH_BINS).rev() {
+        right_box = Aabb::surrounding(&right_box, &bins[b].bbox);
+        right_total += bins[b].count;
+        right_area[b] = right_box.surface_area();
+        right_count[b] = right_total;
+    }
+
+    let area: f64 = bbox.surface_area();
+    let mut best_cost: f64 = f64::INFINITY;
+    let mut best_plane: usize = 0;
+    let mut left_box: Aabb = Aabb::empty();
+    let mut left_total: usize = 0;
+    for plane in 1..SAH_BINS {
+        left_box = Aabb::surrounding(&left_box, &bins[plane - 1].bbox);
+        left_total += bins[plane - 1].count;
+        if left_total == 0 || right_count[plane] == 0 {
+            continue;
+        }
+        let cost: f64 = TRAVERSAL_COST
+            + INTERSECTION_COST
+                * (left_total as f64 * left_box.surface_area()
+                    + right_count[plane] as f64 * right_area[plane])
+                / area;
+        if cost < best_cost {
+            best_cost = cost;
+            best_plane = plane;
+        }
+    }
+
+    let leaf_cost: f64 = INTERSECTION_COST * count as f64;
+    if count <= max_leaf_size && leaf_cost <= best_cost {
+        nodes.push(leaf(bbox));
+        return;
+    }
+
+    let mid: usize = partition(objects, |object| bin_of(object.centroid) < best_plane);
+    split(objects, offset, mid, axis, bbox, max_leaf_size, nodes);
+}
+
+fn split(
+    objects: &mut [BinnedObject],
+    offset: usize,
+    mid: usize,
+    axis: usize,
+    bbox: Aabb,
+    max_leaf_size: usize,
+    nodes: &mut Vec<SahNode>,
+) {
+    let index: usize = nodes.len();
+    nodes.push(SahNode::Interior {
+        bbox,
+        axis,
+        second_child: 0,
+    });
+
+    let (left, right) = objects.split_at_mut(mid);
+    build(left, offset, max_leaf_size, nodes);
+    let second: usize = nodes.len();
+    build(right, offset + mid, max_leaf_size, nodes);
+
+    if let SahNode::Interior { second_child, .. } = &mut nodes[index] {
+        *second_child = second;
+    }
+}
+
+// Moves the objects matching the predicate to the front and returns how many there are
+fn partition(objects: &mut [BinnedObject], predicate: impl Fn(&BinnedObject) -> bool) -> usize {
+    let mut first: usize = 0;
+    for i in 0..objects.len() {
+        if predicate(&objects[i]) {
+            objects.swap(first, i);
+            first += 1;
+        }
+    }
+    first
+}
+
+// Walks the nodes hit by the ray, nearest child first. `hit_leaf` gets the range of objects of each
+// leaf reached and the current interval, and returns the distance of the closest hit it found in it.
+pub(crate) fn traverse(
+    nodes: &[SahNode],
+    ray: Ray,
+    ray_t: Range<f64>,
+    mut hit_leaf: impl FnMut(Range<usize>, Range<f64>) -> Option<f64>,
+) -> bool {
+    let mut hit_anything: bool = false;
+    let mut closest_so_far: f64 = ray_t.end;
+    let mut stack: Vec<usize> = Vec::with_capacity(64);
+    if !nodes.is_empty() {
+        stack.push(0);
+    }
+
+    while let Some(index) = stack.pop() {
+        let node: &SahNode = &nodes[index];
+        if !node.bbox().hit(ray, ray_t.start..closest_so_far) {
+            continue;
+        }
+
+        match node {
+            SahNode::Leaf {
+                first_object,
+                object_count,
+                ..
+            } => {
+                if let Some(t) = hit_leaf(*first_object..first_object + object_count, ray_t.start..closest_so_far) {
+                    hit_anything = true;
+                    closest_so_far = t;
+                }
+            }
+            SahNode::Interior {
+                axis, second_child, ..
+            } => {
+                // Visit the child nearest to the ray origin first, so farther ones get culled by closest_so_far
+                if ray.direction[*axis] < 0.0 {
+                    stack.push(index + 1);
+                    stack.push(*second_child);
+                } else {
+                    stack.push(*second_child);
+                    stack.push(index + 1);
+                }
+            }
+        }
+    }
+
+    hit_anything
+}
+
+impl Hittable for SahBvh {
+    fn hit(&self, ray: Ray, ray_t: Range<f64>, rec: &mut HitRecord) -> bool {
+        traverse(&self.nodes, ray, ray_t, |objects, mut interval| {
+            let mut closest: Option<f64> = None;
+            for object in &self.objects[objects] {
+                if object.hit(ray, interval.clone(), rec) {
+                    closest = Some(rec.t);
+                    interval.end = rec.t;
+                }
+            }
+            closest
+        })
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        match self.nodes.first() {
+            Some(node) => node.bbox().clone(),
+            None => Aabb::empty(),
+        }
+    }
+
+    fn lights(&self) -> Vec<Arc<dyn Hittable>> {
+        gather_lights(&self.objects)
+    }
+
+    fn has_media(&self) -> bool {
+        !self.media.is_empty()
+    }
+
+    fn media<'a>(&'a self, ray: Ray, ray_t: Range<f64>, segments: &mut Vec<MediumSegment<'a>>) {
+        for object in &self.media {
+            object.media(ray, ray_t.clone(), segments);
+        }
+    }
+}
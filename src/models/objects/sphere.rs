@@ -1,19 +1,37 @@
-use std::{ops::Range, rc::Rc};
+use std::{f64::consts::PI, ops::Range, sync::Arc};
 
-use crate::models::{materials::material::Material, ray::Ray};
+use crate::{
+    models::{materials::material::Material, ray::Ray},
+    utils::{
+        math::{random_in_cone, random_unit_vector},
+        onb::Onb,
+        sampler::Sampler,
+    },
+};
 use glam::{DVec3 as Point, DVec3};
 
-use super::global::{HitRecord, Hittable};
+use super::{aabb::Aabb, global::{HitRecord, Hittable}};
 
 pub struct Sphere {
     pub center: Point,
     pub radius: f64,
-    mat:Rc<dyn Material>,
+    mat:Arc<dyn Material>,
+    bbox: Aabb,
 }
 
 impl Sphere {
-    pub fn new(center: Point, radius: f64, mat:Rc<dyn Material>) -> Self {
-        Self { center, radius, mat, }
+    pub fn new(center: Point, radius: f64, mat:Arc<dyn Material>) -> Self {
+        let radius_vector: DVec3 = DVec3::new(radius, radius, radius);
+        let bbox: Aabb = Aabb::from_points(center - radius_vector, center + radius_vector);
+        Self { center, radius, mat, bbox }
+    }
+
+    // Longitude and latitude of a point on the unit sphere, both in 0..1:
+    // u goes around the y axis starting from -x, v goes from the bottom pole (y = -1) to the top one
+    fn get_sphere_uv(point: Point) -> (f64, f64) {
+        let theta: f64 = (-point.y).clamp(-1.0, 1.0).acos();
+        let phi: f64 = (-point.z).atan2(point.x) + PI;
+        (phi / (2.0 * PI), theta / PI)
     }
 }
 
@@ -42,9 +60,45 @@ impl Hittable for Sphere {
         rec.t = root;
         rec.point = ray.at(rec.t);
         rec.normal = (rec.point - self.center) / self.radius;
+        (rec.u, rec.v) = Self::get_sphere_uv(rec.normal);
         rec.set_face_normal(ray);
         rec.mat = self.mat.clone();
+        rec.vertex_color = None;
+        rec.sampled_light = self.is_light();
         true
     }
+
+    fn bounding_box(&self) -> Aabb {
+        self.bbox.clone()
+    }
+
+    fn is_light(&self) -> bool {
+        self.mat.is_emissive()
+    }
+
+    // From outside, the directions are uniform in the cone the sphere fills. From inside the sphere covers
+    // every direction, once each, so they're uniform over all of them.
+    fn pdf_value(&self, origin: Point, direction: DVec3) -> f64 {
+        let mut rec: HitRecord = HitRecord::new();
+        if !self.hit(Ray::new(origin, direction), 0.001..f64::INFINITY, &mut rec) {
+            return 0.0;
+        }
+        let distance_squared: f64 = (self.center - origin).length_squared();
+        if distance_squared <= self.radius * self.radius {
+            return 1.0 / (4.0 * PI);
+        }
+        let cos_theta_max: f64 = (1.0 - self.radius * self.radius / distance_squared).sqrt();
+        1.0 / (2.0 * PI * (1.0 - cos_theta_max))
+    }
+
+    fn random(&self, origin: Point, sampler: &mut Sampler) -> DVec3 {
+        let direction: DVec3 = self.center - origin;
+        let distance_squared: f64 = direction.length_squared();
+        if distance_squared <= self.radius * self.radius {
+            return random_unit_vector(sampler);
+        }
+        let cos_theta_max: f64 = (1.0 - self.radius * self.radius / distance_squared).sqrt();
+        Onb::new(direction).transform(random_in_cone(cos_theta_max, sampler))
+    }
 }
 
@@ -0,0 +1,156 @@
+use std::{ops::Range, sync::Arc};
+
+use glam::{DVec2, DVec3, DVec3 as Color, DVec3 as Point};
+
+use crate::models::{materials::material::Material, ray::Ray};
+
+use super::{
+    aabb::Aabb,
+    global::{HitRecord, Hittable},
+};
+
+pub struct Triangle {
+    pub a: Point,
+    pub b: Point,
+    pub c: Point,
+    // Per vertex normals, interpolated over the triangle for smooth shading
+    pub normals: Option<[DVec3; 3]>,
+    // Per vertex texture coordinates, without them u and v are the barycentric coordinates of the hit
+    pub uvs: Option<[DVec2; 3]>,
+    // Per vertex colors, interpolated over the triangle to tint the material
+    pub colors: Option<[Color; 3]>,
+    pub mat: Arc<dyn Material>,
+    normal: DVec3,
+    bbox: Aabb,
+}
+
+impl Triangle {
+    pub fn new(a: Point, b: Point, c: Point, mat: Arc<dyn Material>) -> Self {
+        Self::with_attributes(a, b, c, None, None, mat)
+    }
+
+    pub fn with_attributes(
+        a: Point,
+        b: Point,
+        c: Point,
+        normals: Option<[DVec3; 3]>,
+        uvs: Option<[DVec2; 3]>,
+        mat: Arc<dyn Material>,
+    ) -> Self {
+        let normal: DVec3 = (b - a).cross(c - a).normalize();
+        let bbox: Aabb = Aabb::from_points(a.min(b).min(c), a.max(b).max(c));
+
+        Self {
+            a,
+            b,
+            c,
+            normals,
+            uvs,
+            colors: None,
+            mat,
+            normal,
+            bbox,
+        }
+    }
+}
+
+// Where a ray crosses a triangle: the distance along the ray and the barycentric coordinates of b and c
+#[derive(Clone, Copy)]
+pub(crate) struct TriangleHit {
+    pub t: f64,
+    pub beta: f64,
+    pub gamma: f64,
+}
+
+// Möller–Trumbore: solving origin + t * direction = a + beta * (b - a) + gamma * (c - a)
+pub(crate) fn intersect_triangle(a: Point, b: Point, c: Point, ray: Ray, ray_t: &Range<f64>) -> Option<TriangleHit> {
+    let edge1: DVec3 = b - a;
+    let edge2: DVec3 = c - a;
+    let p: DVec3 = ray.direction.cross(edge2);
+    let determinant: f64 = edge1.dot(p);
+
+    // The ray is parallel to the triangle's plane
+    if determinant.abs() < 1e-12 {
+        return None;
+    }
+    let inverse_determinant: f64 = 1.0 / determinant;
+
+    let to_origin: DVec3 = ray.origin - a;
+    let beta: f64 = to_origin.dot(p) * inverse_determinant;
+    if !(0.0..=1.0).contains(&beta) {
+        return None;
+    }
+
+    let q: DVec3 = to_origin.cross(edge1);
+    let gamma: f64 = ray.direction.dot(q) * inverse_determinant;
+    if gamma < 0.0 || beta + gamma > 1.0 {
+        return None;
+    }
+
+    let t: f64 = edge2.dot(q) * inverse_determinant;
+    if !ray_t.contains(&t) {
+        return None;
+    }
+    Some(TriangleHit { t, beta, gamma })
+}
+
+// Fills everything but the material, the vertex attributes are interpolated at the hit
+pub(crate) fn set_triangle_record(
+    rec: &mut HitRecord,
+    ray: Ray,
+    hit: TriangleHit,
+    normal: DVec3,
+    normals: Option<[DVec3; 3]>,
+    uvs: Option<[DVec2; 3]>,
+    colors: Option<[Color; 3]>,
+) {
+    let TriangleHit { t, beta, gamma } = hit;
+    let alpha: f64 = 1.0 - beta - gamma;
+
+    rec.t = t;
+    rec.point = ray.at(t);
+    rec.normal = normal;
+    rec.set_face_normal(ray);
+
+    // The side is decided by the geometric normal, the interpolated one only changes the shading
+    if let Some([na, nb, nc]) = normals {
+        let shading_normal: DVec3 = (alpha * na + beta * nb + gamma * nc).normalize();
+        if shading_normal.is_finite() {
+            rec.normal = if shading_normal.dot(rec.normal) < 0.0 {
+                -shading_normal
+            } else {
+                shading_normal
+            };
+        }
+    }
+
+    rec.sampled_light = false;
+    rec.vertex_color = colors.map(|[color_a, color_b, color_c]| alpha * color_a + beta * color_b + gamma * color_c);
+
+    match uvs {
+        Some([uv_a, uv_b, uv_c]) => {
+            let uv: DVec2 = alpha * uv_a + beta * uv_b + gamma * uv_c;
+            rec.u = uv.x;
+            rec.v = uv.y;
+        }
+        None => {
+            rec.u = beta;
+            rec.v = gamma;
+        }
+    }
+}
+
+impl Hittable for Triangle {
+    fn hit(&self, ray: Ray, ray_t: Range<f64>, rec: &mut HitRecord) -> bool {
+        let Some(hit) = intersect_triangle(self.a, self.b, self.c, ray, &ray_t) else {
+            return false;
+        };
+        set_triangle_record(rec, ray, hit, self.normal, self.normals, self.uvs, self.colors);
+        rec.mat = self.mat.clone();
+        true
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        self.bbox.clone()
+    }
+}
@@ -0,0 +1,627 @@
+use std::{collections::HashMap, fs, io, path::Path, sync::Arc};
+
+use glam::{DMat4, DVec3, DVec3 as Point, DVec3 as Color};
+
+use super::{
+    camera::Camera,
+    instances::{
+        rotate::Rotate,
+        transform::{look_at, rotation, scale, Transform},
+        translate::Translate,
+    },
+    lights::{
+        directional_light::DirectionalLight,
+        light::{Light, LightList},
+        point_light::PointLight,
+        spot_light::SpotLight,
+    },
+    materials::{
+        dielectric::Dielectric,
+        diffuse_light::DiffuseLight,
+        isotropic::Isotropic,
+        material::{Lambertian, Material},
+        metal::Metal,
+    },
+    objects::{
+        constant_medium::{ConstantMedium, Haze},
+        cylinder::Cylinder,
+        global::{Hittable, HittableList},
+        grid_volume::{DensityGrid, GridVolume},
+        quad::{box_shape, Quad},
+        sphere::Sphere,
+        triangle::Triangle,
+    },
+    textures::{
+        checker::{CheckerTexture, UvCheckerTexture},
+        image_texture::{ImageTexture, WrapMode},
+        noise::{NoisePattern, NoiseTexture},
+        texture::{SolidColor, Texture},
+    },
+};
+use crate::utils::{grid::load_raw_grid, input::load_mesh, sampler::Sampler};
+
+// A world and the camera looking at it, as described by a scene file.
+//
+// Scene files are made of one statement per line, '#' starts a comment:
+//
+//     camera <setting> <values...>        fov, width, aspect_ratio, look_from, look_at, vup,
+//                                         samples_per_pixel, max_depth, defocus_angle, focus_dist,
+//                                         background, brightness, threads, seed, roulette_depth
+//     camera haze <density> <r g b> <extent>  medium filling the whole scene, `extent` is the distance
+//                                         rays leaving the scene still travel through it
+//     texture <name> solid <r g b>
+//     texture <name> image <file> [repeat|clamp]  PPM, PNG or PFM image, repeated by default
+//     texture <name> checker <size> <even> <odd>   3D checker, even and odd are colors or textures
+//     texture <name> uv_checker <columns> <rows> <even> <odd>
+//     texture <name> noise|turbulence|marble|wood <scale> [<dark r g b> <light r g b>]
+//     material <name> lambertian <r g b | texture>
+//     material <name> metal <r g b | texture> <fuzziness>
+//     material <name> dielectric <refraction index>
+//     material <name> diffuse_light <r g b | texture>
+//     material <name> isotropic <r g b | texture>  scatters in every direction, for volumes
+//     light point <position> <r g b> <intensity>    fading with the square of the distance
+//     light spot <position> <target> <r g b> <intensity> <inner angle> <outer angle>
+//                                         angles in degrees from the axis, fading from inner to outer
+//     light directional <direction> <r g b> <intensity> [angular diameter]
+//                                         sun light going along `direction`, the diameter in degrees
+//     sphere <center> <radius> <material>
+//     quad <q> <u> <v> <material>
+//     box <a> <b> <material>
+//     cylinder <origin> <axis> <radius> <height> <material>
+//     triangle <a> <b> <c> <material>
+//     mesh <file> <material>              OBJ, PLY or STL file, the material is used by faces without an MTL one
+//     medium <density> <r g b | texture> <object>   smoke or fog filling a convex object,
+//                                         the material of the object is not used
+//     volume <min> <max> <absorption r g b> <scattering r g b> <grid> [emission <r g b>]
+//                                         varying density over the box, the grid being either
+//                                         raw <file> <nx ny nz>   bytes or little endian f32, x first
+//                                         noise <resolution> <scale>   procedural cloud, at most 256 wide
+//
+// Points and vectors are written as three numbers. Any object can be followed by instances,
+// applied from left to right:
+//
+//     rotate <angle>                      around the y axis
+//     translate <x y z>
+//     rotate_axis <axis> <angle>          around any axis
+//     scale <x y z>                       no factor can be zero
+//     look_at <from> <to> <up>            moves the object to `from`, its +z axis pointing at `to`
+//
+// Mesh and image files are looked up relative to the scene file.
+
+// A 256 cells wide noise grid already holds 16 million densities
+const MAX_NOISE_RESOLUTION: usize = 256;
+
+pub struct Scene {
+    pub world: HittableList,
+    pub camera: Camera,
+}
+
+struct CameraSettings {
+    fov: f64,
+    image_width: i32,
+    aspect_ratio: f64,
+    look_from: Point,
+    look_at: Point,
+    vup: DVec3,
+    samples_per_pixel: f64,
+    max_depth: f64,
+    defocus_angle: f64,
+    focus_dist: f64,
+    background: Color,
+    brightness: f64,
+    threads: Option<usize>,
+    seed: u64,
+    // Density, color and extent
+    haze: Option<(f64, Color, f64)>,
+    roulette_depth: f64,
+}
+
+impl Default for CameraSettings {
+    fn default() -> Self {
+        Self {
+            fov: 90.0,
+            image_width: 800,
+            aspect_ratio: 16.0 / 9.0,
+            look_from: Point::new(0.0, 0.0, 0.0),
+            look_at: Point::new(0.0, 0.0, -1.0),
+            vup: DVec3::new(0.0, 1.0, 0.0),
+            samples_per_pixel: 100.0,
+            max_depth: 50.0,
+            defocus_angle: 0.0,
+            focus_dist: 10.0,
+            background: Color::new(0.7, 0.8, 1.0),
+            brightness: 1.0,
+            threads: None,
+            seed: 0,
+            haze: None,
+            roulette_depth: 5.0,
+        }
+    }
+}
+
+impl CameraSettings {
+    fn build(&self) -> Camera {
+        let mut camera: Camera = Camera::new(
+            self.fov,
+            self.image_width,
+            self.look_from,
+            self.look_at,
+            self.vup,
+            self.aspect_ratio,
+            self.samples_per_pixel,
+            self.max_depth,
+            self.defocus_angle,
+            self.focus_dist,
+            self.background,
+            self.brightness,
+        );
+        if let Some(threads) = self.threads {
+            camera.threads = threads;
+        }
+        camera.seed = self.seed;
+        camera.roulette_depth = self.roulette_depth;
+        camera.haze = self
+            .haze
+            .map(|(density, color, extent)| Haze::new(density, color, extent));
+        camera
+    }
+}
+
+pub fn load_scene(path: &Path) -> io::Result<Scene> {
+    let text: String = fs::read_to_string(path)?;
+    let directory: &Path = path.parent().unwrap_or(Path::new(""));
+    parse_scene_in(&text, directory).map_err(|error| io::Error::new(error.kind(), format!("{}:{}", path.display(), error)))
+}
+
+pub fn parse_scene(text: &str) -> io::Result<Scene> {
+    parse_scene_in(text, Path::new(""))
+}
+
+fn parse_scene_in(text: &str, directory: &Path) -> io::Result<Scene> {
+    let mut settings: CameraSettings = CameraSettings::default();
+    let mut textures: HashMap<String, Arc<dyn Texture>> = HashMap::new();
+    // Random numbers of the procedural textures and volumes, so a scene file always gives the same patterns
+    let mut sampler: Sampler = Sampler::new(0);
+    let mut materials: HashMap<String, Arc<dyn Material>> = HashMap::new();
+    let mut world: HittableList = HittableList::new();
+    let mut lights: LightList = LightList::new();
+
+    for (index, line) in text.lines().enumerate() {
+        let content: &str = line.split('#').next().unwrap_or_default();
+        let mut tokens: Tokens = Tokens {
+            words: content.split_whitespace().collect(),
+            position: 0,
+        };
+        if tokens.words.is_empty() {
+            continue;
+        }
+
+        let result: io::Result<()> = match tokens.word("statement") {
+            Ok("camera") => parse_camera_setting(&mut tokens, &mut settings),
+            Ok("texture") => parse_texture(&mut tokens, &textures, &mut sampler, directory).map(|(name, texture)| {
+                textures.insert(name, texture);
+            }),
+            Ok("material") => parse_material(&mut tokens, &textures).map(|(name, material)| {
+                materials.insert(name, material);
+            }),
+            Ok("light") => parse_light(&mut tokens).map(|light| lights.add(light)),
+            Ok(_) => {
+                tokens.position = 0;
+                parse_object(&mut tokens, &textures, &materials, &mut sampler, directory).map(|object| world.add(object))
+            }
+            Err(error) => Err(error),
+        }
+        .and_then(|_| tokens.finish());
+
+        if let Err(error) = result {
+            return Err(io::Error::new(error.kind(), format!("{}: {}", index + 1, error)));
+        }
+    }
+
+    let mut camera: Camera = settings.build();
+    camera.lights = lights;
+    Ok(Scene { world, camera })
+}
+
+fn parse_camera_setting(tokens: &mut Tokens, settings: &mut CameraSettings) -> io::Result<()> {
+    match tokens.word("camera setting")? {
+        "fov" => settings.fov = tokens.number("fov")?,
+        "width" => {
+            let width: usize = tokens.integer("width")?;
+            settings.image_width = match i32::try_from(width) {
+                Ok(width) if width > 0 => width,
+                _ => return Err(invalid(format!("width has to be a positive integer, got {}", width))),
+            };
+        }
+        "aspect_ratio" => {
+            let aspect_ratio: f64 = tokens.number("aspect ratio")?;
+            if aspect_ratio <= 0.0 {
+                return Err(invalid(format!("aspect ratio has to be positive, got {}", aspect_ratio)));
+            }
+            settings.aspect_ratio = aspect_ratio;
+        }
+        "look_from" => settings.look_from = tokens.vector("look_from")?,
+        "look_at" => settings.look_at = tokens.vector("look_at")?,
+        "vup" => settings.vup = tokens.vector("vup")?,
+        "samples_per_pixel" => {
+            let samples_per_pixel: usize = tokens.integer("samples per pixel")?;
+            if samples_per_pixel == 0 {
+                return Err(invalid("samples per pixel has to be positive, got 0".to_string()));
+            }
+            settings.samples_per_pixel = samples_per_pixel as f64;
+        }
+        "max_depth" => settings.max_depth = tokens.integer("max depth")? as f64,
+        "defocus_angle" => settings.defocus_angle = tokens.number("defocus angle")?,
+        "focus_dist" => settings.focus_dist = tokens.number("focus distance")?,
+        "background" => settings.background = tokens.vector("background")?,
+        "brightness" => settings.brightness = tokens.number("brightness")?,
+        "threads" => settings.threads = Some(tokens.integer("threads")?),
+        "seed" => settings.seed = tokens.integer("seed")? as u64,
+        "roulette_depth" => settings.roulette_depth = tokens.integer("roulette depth")? as f64,
+        "haze" => {
+            let density: f64 = tokens.number("haze density")?;
+            let color: Color = tokens.vector("haze color")?;
+            let extent: f64 = tokens.number("haze extent")?;
+            if density <= 0.0 || extent < 0.0 {
+                return Err(invalid("haze density has to be positive and its extent not negative".to_string()));
+            }
+            settings.haze = Some((density, color, extent));
+        }
+        setting => return Err(invalid(format!("unknown camera setting {:?}", setting))),
+    }
+    Ok(())
+}
+
+fn parse_texture(
+    tokens: &mut Tokens,
+    textures: &HashMap<String, Arc<dyn Texture>>,
+    sampler: &mut Sampler,
+    directory: &Path,
+) -> io::Result<(String, Arc<dyn Texture>)> {
+    let name: String = tokens.word("texture name")?.to_string();
+    let texture: Arc<dyn Texture> = match tokens.word("texture type")? {
+        "solid" => Arc::new(SolidColor::new(tokens.vector("color")?)),
+        "image" => {
+            let file: &str = tokens.word("image file")?;
+            let wrap: WrapMode = if tokens.is_empty() {
+                WrapMode::Repeat
+            } else {
+                let mode: &str = tokens.word("wrap mode")?;
+                WrapMode::from_name(mode).ok_or_else(|| invalid(format!("unknown wrap mode {:?}", mode)))?
+            };
+            Arc::new(ImageTexture::load(&directory.join(file), wrap)?)
+        }
+        "checker" => {
+            let size: f64 = tokens.number("checker size")?;
+            Arc::new(CheckerTexture::new(
+                size,
+                tokens.texture("even color", textures)?,
+                tokens.texture("odd color", textures)?,
+            ))
+        }
+        "uv_checker" => {
+            let columns: f64 = tokens.number("columns")?;
+            let rows: f64 = tokens.number("rows")?;
+            Arc::new(UvCheckerTexture::new(
+                columns,
+                rows,
+                tokens.texture("even color", textures)?,
+                tokens.texture("odd color", textures)?,
+            ))
+        }
+        kind => match NoisePattern::from_name(kind) {
+            Some(pattern) => {
+                let scale: f64 = tokens.number("noise scale")?;
+                if tokens.is_empty() {
+                    Arc::new(NoiseTexture::new(pattern, scale, sampler))
+                } else {
+                    let dark: Color = tokens.vector("dark color")?;
+                    let light: Color = tokens.vector("light color")?;
+                    Arc::new(NoiseTexture::with_colors(pattern, scale, dark, light, sampler))
+                }
+            }
+            None => return Err(invalid(format!("unknown texture type {:?}", kind))),
+        },
+    };
+    Ok((name, texture))
+}
+
+fn parse_material(
+    tokens: &mut Tokens,
+    textures: &HashMap<String, Arc<dyn Texture>>,
+) -> io::Result<(String, Arc<dyn Material>)> {
+    let name: String = tokens.word("material name")?.to_string();
+    let material: Arc<dyn Material> = match tokens.word("material type")? {
+        "lambertian" => Arc::new(Lambertian::from_texture(tokens.texture("albedo", textures)?)),
+        "metal" => Arc::new(Metal::from_texture(
+            tokens.texture("albedo", textures)?,
+            tokens.number("fuzziness")?,
+        )),
+        "dielectric" => Arc::new(Dielectric::new(tokens.number("refraction index")?)),
+        "diffuse_light" => Arc::new(DiffuseLight::from_texture(tokens.texture("emitted color", textures)?)),
+        "isotropic" => Arc::new(Isotropic::from_texture(tokens.texture("albedo", textures)?)),
+        kind => return Err(invalid(format!("unknown material type {:?}", kind))),
+    };
+    Ok((name, material))
+}
+
+fn parse_light(tokens: &mut Tokens) -> io::Result<Arc<dyn Light>> {
+    let light: Arc<dyn Light> = match tokens.word("light type")? {
+        "point" => {
+            let position: Point = tokens.vector("position")?;
+            Arc::new(PointLight::new(position, tokens.vector("color")?, tokens.number("intensity")?))
+        }
+        "spot" => {
+            let position: Point = tokens.vector("position")?;
+            let target: Point = tokens.vector("target")?;
+            let color: Color = tokens.vector("color")?;
+            let intensity: f64 = tokens.number("intensity")?;
+            let inner_angle: f64 = tokens.number("inner angle")?;
+            let outer_angle: f64 = tokens.number("outer angle")?;
+            if target == position {
+                return Err(invalid("a spot light can't point at its own position".to_string()));
+            }
+            Arc::new(SpotLight::looking_at(position, target, color, intensity, inner_angle, outer_angle))
+        }
+        "directional" => {
+            let direction: DVec3 = tokens.vector("direction")?;
+            let color: Color = tokens.vector("color")?;
+            let intensity: f64 = tokens.number("intensity")?;
+            let angular_diameter: f64 = if tokens.is_empty() { 0.0 } else { tokens.number("angular diameter")? };
+            if direction == DVec3::ZERO {
+                return Err(invalid("a directional light needs a direction".to_string()));
+            }
+            Arc::new(DirectionalLight::with_angular_diameter(direction, color, intensity, angular_diameter))
+        }
+        kind => return Err(invalid(format!("unknown light type {:?}", kind))),
+    };
+    Ok(light)
+}
+
+fn parse_object(
+    tokens: &mut Tokens,
+    textures: &HashMap<String, Arc<dyn Texture>>,
+    materials: &HashMap<String, Arc<dyn Material>>,
+    sampler: &mut Sampler,
+    directory: &Path,
+) -> io::Result<Arc<dyn Hittable>> {
+    let mut object: Arc<dyn Hittable> = match tokens.word("object")? {
+        "sphere" => {
+            let center: Point = tokens.vector("center")?;
+            let radius: f64 = tokens.number("radius")?;
+            Arc::new(Sphere::new(center, radius, tokens.material(materials)?))
+        }
+        "quad" => {
+            let q: Point = tokens.vector("corner")?;
+            let u: DVec3 = tokens.vector("u")?;
+            let v: DVec3 = tokens.vector("v")?;
+            Arc::new(Quad::new(q, u, v, tokens.material(materials)?))
+        }
+        "box" => {
+            let a: Point = tokens.vector("first corner")?;
+            let b: Point = tokens.vector("opposite corner")?;
+            box_shape(a, b, tokens.material(materials)?)
+        }
+        "cylinder" => {
+            let origin: Point = tokens.vector("origin")?;
+            let axis: DVec3 = tokens.vector("axis")?;
+            let radius: f64 = tokens.number("radius")?;
+            let height: f64 = tokens.number("height")?;
+            if axis == DVec3::ZERO {
+                return Err(invalid("a cylinder needs an axis".to_string()));
+            }
+            Arc::new(Cylinder::new(origin, axis, radius, height, tokens.material(materials)?))
+        }
+        "triangle" => {
+            let a: Point = tokens.vector("first vertex")?;
+            let b: Point = tokens.vector("second vertex")?;
+            let c: Point = tokens.vector("third vertex")?;
+            Arc::new(Triangle::new(a, b, c, tokens.material(materials)?))
+        }
+        "mesh" => {
+            let file: &str = tokens.word("mesh file")?;
+            Arc::new(load_mesh(&directory.join(file), tokens.material(materials)?)?)
+        }
+        "medium" => {
+            let density: f64 = tokens.number("medium density")?;
+            if density <= 0.0 {
+                return Err(invalid("medium density has to be positive".to_string()));
+            }
+            let texture: Arc<dyn Texture> = tokens.texture("medium color", textures)?;
+            // The boundary takes the rest of the line, its instances included
+            let boundary: Arc<dyn Hittable> = parse_object(tokens, textures, materials, sampler, directory)?;
+            Arc::new(ConstantMedium::from_texture(boundary, density, texture))
+        }
+        "volume" => {
+            let a: Point = tokens.vector("first corner")?;
+            let b: Point = tokens.vector("opposite corner")?;
+            if (a - b).abs().min_element() == 0.0 {
+                return Err(invalid("volume box can't be flat".to_string()));
+            }
+            let absorption: Color = tokens.vector("absorption")?;
+            let scattering: Color = tokens.vector("scattering")?;
+            if absorption.min(scattering).min_element() < 0.0 {
+                return Err(invalid("volume coefficients can't be negative".to_string()));
+            }
+            let grid: DensityGrid = match tokens.word("grid")? {
+                "raw" => {
+                    let file: &str = tokens.word("grid file")?;
+                    let resolution: [usize; 3] = [
+                        tokens.integer("grid resolution")?,
+                        tokens.integer("grid resolution")?,
+                        tokens.integer("grid resolution")?,
+                    ];
+                    load_raw_grid(&directory.join(file), resolution)?
+                }
+                "noise" => {
+                    let resolution: usize = tokens.integer("grid resolution")?;
+                    if resolution > MAX_NOISE_RESOLUTION {
+                        return Err(invalid(format!(
+                            "noise grid resolution can't be over {}, got {}",
+                            MAX_NOISE_RESOLUTION, resolution
+                        )));
+                    }
+                    let scale: f64 = tokens.number("noise scale")?;
+                    DensityGrid::from_noise([resolution; 3], scale, sampler)
+                }
+                grid => return Err(invalid(format!("unknown grid {:?}", grid))),
+            };
+            let mut volume: GridVolume = GridVolume::new(Arc::new(grid), a.min(b), a.max(b), absorption, scattering);
+            if tokens.words.get(tokens.position) == Some(&"emission") {
+                tokens.position += 1;
+                volume.emission = tokens.vector("emission")?;
+            }
+            Arc::new(volume)
+        }
+        statement => return Err(invalid(format!("unknown statement {:?}", statement))),
+    };
+
+    // Following matrix instances are merged in a single Transform
+    let mut matrix: Option<DMat4> = None;
+    while !tokens.is_empty() {
+        let step: DMat4 = match tokens.word("instance")? {
+            "rotate_axis" => {
+                let axis: DVec3 = tokens.vector("rotation axis")?;
+                if axis == DVec3::ZERO {
+                    return Err(invalid("rotation axis can't be zero".to_string()));
+                }
+                rotation(axis, tokens.number("angle")?)
+            }
+            "scale" => {
+                let factors: DVec3 = tokens.vector("scale")?;
+                if factors.x * factors.y * factors.z == 0.0 {
+                    return Err(invalid("scale factors can't be zero".to_string()));
+                }
+                scale(factors)
+            }
+            "look_at" => {
+                let from: Point = tokens.vector("look_at origin")?;
+                let to: Point = tokens.vector("look_at target")?;
+                let up: DVec3 = tokens.vector("look_at up")?;
+                if (to - from).cross(up) == DVec3::ZERO {
+                    return Err(invalid("look_at needs a target away from the origin and an up not along it".to_string()));
+                }
+                look_at(from, to, up)
+            }
+            instance => {
+                if let Some(matrix) = matrix.take() {
+                    object = transformed(object, matrix)?;
+                }
+                object = match instance {
+                    "rotate" => Arc::new(Rotate::new(object, tokens.number("angle")?)),
+                    "translate" => Arc::new(Translate::new(object, tokens.vector("offset")?)),
+                    instance => return Err(invalid(format!("unknown instance {:?}", instance))),
+                };
+                continue;
+            }
+        };
+        matrix = Some(step * matrix.unwrap_or(DMat4::IDENTITY));
+    }
+    if let Some(matrix) = matrix {
+        object = transformed(object, matrix)?;
+    }
+    Ok(object)
+}
+
+fn transformed(object: Arc<dyn Hittable>, matrix: DMat4) -> io::Result<Arc<dyn Hittable>> {
+    match Transform::new(object, matrix) {
+        Some(transform) => Ok(Arc::new(transform)),
+        None => Err(invalid("the instances flatten the object, their matrix can't be inverted".to_string())),
+    }
+}
+
+struct Tokens<'a> {
+    words: Vec<&'a str>,
+    position: usize,
+}
+
+impl<'a> Tokens<'a> {
+    fn is_empty(&self) -> bool {
+        self.position >= self.words.len()
+    }
+
+    fn word(&mut self, name: &str) -> io::Result<&'a str> {
+        let word: &str = self
+            .words
+            .get(self.position)
+            .ok_or_else(|| invalid(format!("missing {}", name)))?;
+        self.position += 1;
+        Ok(word)
+    }
+
+    // Numbers can also be written as a fraction, like 16/9 for the aspect ratio
+    fn number(&mut self, name: &str) -> io::Result<f64> {
+        let word: &str = self.word(name)?;
+        let parsed: Option<f64> = match word.split_once('/') {
+            Some((numerator, denominator)) => numerator
+                .parse::<f64>()
+                .ok()
+                .zip(denominator.parse::<f64>().ok())
+                .map(|(numerator, denominator)| numerator / denominator),
+            None => word.parse::<f64>().ok(),
+        };
+        parsed
+            .filter(|number| number.is_finite())
+            .ok_or_else(|| invalid(format!("invalid {} {:?}", name, word)))
+    }
+
+    fn integer(&mut self, name: &str) -> io::Result<usize> {
+        let word: &str = self.word(name)?;
+        if word.parse::<i64>().is_ok_and(|integer| integer < 0) {
+            return Err(invalid(format!("{} can't be negative, got {}", name, word)));
+        }
+        word.parse::<usize>()
+            .map_err(|_| invalid(format!("invalid {} {:?}", name, word)))
+    }
+
+    fn vector(&mut self, name: &str) -> io::Result<DVec3> {
+        Ok(DVec3::new(self.number(name)?, self.number(name)?, self.number(name)?))
+    }
+
+    // Either three numbers for a plain color or the name of a texture
+    fn texture(&mut self, name: &str, textures: &HashMap<String, Arc<dyn Texture>>) -> io::Result<Arc<dyn Texture>> {
+        let word: &str = self.words.get(self.position).copied().unwrap_or_default();
+        match textures.get(word) {
+            Some(texture) => {
+                self.position += 1;
+                Ok(texture.clone())
+            }
+            None if word.is_empty() || word.starts_with(|c: char| c.is_ascii_digit() || c == '-' || c == '+' || c == '.') => {
+                Ok(Arc::new(SolidColor::new(self.vector(name)?)))
+            }
+            None => Err(invalid(format!("unknown texture {:?}", word))),
+        }
+    }
+
+    fn material(&mut self, materials: &HashMap<String, Arc<dyn Material>>) -> io::Result<Arc<dyn Material>> {
+        let name: &str = self.word("material")?;
+        materials
+            .get(name)
+            .cloned()
+            .ok_or_else(|| invalid(format!("unknown material {:?}", name)))
+    }
+
+    fn finish(&self) -> io::Result<()> {
+        match self.words.get(self.position) {
+            Some(word) => Err(invalid(format!("unexpected {:?}", word))),
+            None => Ok(()),
+        }
+    }
+}
+
+fn invalid(message: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn degenerate_camera_settings_are_rejected() {
+        for setting in ["width 0", "width 3000000000", "samples_per_pixel 0", "aspect_ratio 0", "aspect_ratio -16/9"] {
+            assert!(parse_scene(&format!("camera {}\n", setting)).is_err(), "{}", setting);
+        }
+        assert!(parse_scene("camera width 20\ncamera samples_per_pixel 1\ncamera aspect_ratio 16/9\n").is_ok());
+    }
+}
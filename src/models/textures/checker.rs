@@ -0,0 +1,71 @@
+use std::sync::Arc;
+
+use glam::{DVec3 as Color, DVec3 as Point};
+
+use super::texture::{SolidColor, Texture};
+
+// 3D checker of cubes with sides of `scale`, evaluated from the hit point so it doesn't need texture coordinates
+pub struct CheckerTexture {
+    inv_scale: f64,
+    pub even: Arc<dyn Texture>,
+    pub odd: Arc<dyn Texture>,
+}
+
+impl CheckerTexture {
+    pub fn new(scale: f64, even: Arc<dyn Texture>, odd: Arc<dyn Texture>) -> Self {
+        Self {
+            inv_scale: 1.0 / scale,
+            even,
+            odd,
+        }
+    }
+
+    pub fn from_colors(scale: f64, even: Color, odd: Color) -> Self {
+        Self::new(scale, Arc::new(SolidColor::new(even)), Arc::new(SolidColor::new(odd)))
+    }
+}
+
+impl Texture for CheckerTexture {
+    fn value(&self, u: f64, v: f64, point: Point) -> Color {
+        let cell: Point = (self.inv_scale * point).floor();
+        if (cell.x + cell.y + cell.z).rem_euclid(2.0) == 0.0 {
+            self.even.value(u, v, point)
+        } else {
+            self.odd.value(u, v, point)
+        }
+    }
+}
+
+// 2D checker over the texture coordinates, `columns` squares along u and `rows` along v
+pub struct UvCheckerTexture {
+    pub columns: f64,
+    pub rows: f64,
+    pub even: Arc<dyn Texture>,
+    pub odd: Arc<dyn Texture>,
+}
+
+impl UvCheckerTexture {
+    pub fn new(columns: f64, rows: f64, even: Arc<dyn Texture>, odd: Arc<dyn Texture>) -> Self {
+        Self {
+            columns,
+            rows,
+            even,
+            odd,
+        }
+    }
+
+    pub fn from_colors(columns: f64, rows: f64, even: Color, odd: Color) -> Self {
+        Self::new(columns, rows, Arc::new(SolidColor::new(even)), Arc::new(SolidColor::new(odd)))
+    }
+}
+
+impl Texture for UvCheckerTexture {
+    fn value(&self, u: f64, v: f64, point: Point) -> Color {
+        let cell: f64 = (u * self.columns).floor() + (v * self.rows).floor();
+        if cell.rem_euclid(2.0) == 0.0 {
+            self.even.value(u, v, point)
+        } else {
+            self.odd.value(u, v, point)
+        }
+    }
+}
@@ -0,0 +1,88 @@
+use std::{io, path::Path};
+
+use glam::{DVec3 as Color, DVec3 as Point};
+
+use crate::{models::image::Image, utils::input::load_image};
+
+use super::texture::Texture;
+
+// What happens to texture coordinates outside of 0..1
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum WrapMode {
+    // The image is tiled
+    Repeat,
+    // The border pixels are stretched
+    Clamp,
+}
+
+impl WrapMode {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "repeat" => Some(WrapMode::Repeat),
+            "clamp" => Some(WrapMode::Clamp),
+            _ => None,
+        }
+    }
+
+    fn texel(self, coordinate: i64, size: usize) -> usize {
+        match self {
+            WrapMode::Repeat => coordinate.rem_euclid(size as i64) as usize,
+            WrapMode::Clamp => coordinate.clamp(0, size as i64 - 1) as usize,
+        }
+    }
+}
+
+// Image lookup with bilinear filtering, u goes from the left to the right and v from the bottom to the top
+pub struct ImageTexture {
+    pub image: Image,
+    pub wrap: WrapMode,
+}
+
+impl ImageTexture {
+    pub fn new(image: Image, wrap: WrapMode) -> Self {
+        Self { image, wrap }
+    }
+
+    // Any image load_image can read (PPM, PNG or PFM), the pixels are linear colors
+    pub fn load(path: &Path, wrap: WrapMode) -> io::Result<Self> {
+        let image: Image = load_image(path)?;
+        if image.width == 0 || image.height == 0 {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("{}: the texture image is empty", path.display()),
+            ));
+        }
+        Ok(Self::new(image, wrap))
+    }
+}
+
+impl Texture for ImageTexture {
+    fn value(&self, u: f64, v: f64, _point: Point) -> Color {
+        let width: usize = self.image.width;
+        let height: usize = self.image.height;
+        if width == 0 || height == 0 {
+            // Cyan, to make a missing texture stand out
+            return Color::new(0.0, 1.0, 1.0);
+        }
+
+        // Pixel centers sit at half coordinates, and the image rows go from the top to the bottom
+        let x: f64 = u * width as f64 - 0.5;
+        let y: f64 = (1.0 - v) * height as f64 - 0.5;
+        if !x.is_finite() || !y.is_finite() {
+            return Color::new(0.0, 1.0, 1.0);
+        }
+        let x0: f64 = x.floor();
+        let y0: f64 = y.floor();
+        let fx: f64 = x - x0;
+        let fy: f64 = y - y0;
+
+        let i0: usize = self.wrap.texel(x0 as i64, width);
+        let i1: usize = self.wrap.texel(x0 as i64 + 1, width);
+        let j0: usize = self.wrap.texel(y0 as i64, height);
+        let j1: usize = self.wrap.texel(y0 as i64 + 1, height);
+
+        let top: Color = self.image.get(i0, j0).lerp(self.image.get(i1, j0), fx);
+        let bottom: Color = self.image.get(i0, j1).lerp(self.image.get(i1, j1), fx);
+        top.lerp(bottom, fy)
+    }
+}
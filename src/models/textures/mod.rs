@@ -0,0 +1,5 @@
+pub mod texture;
+pub mod image_texture;
+pub mod checker;
+pub mod perlin;
+pub mod noise;
\ No newline at end of file
@@ -0,0 +1,76 @@
+use glam::{DVec3 as Color, DVec3 as Point};
+
+use crate::utils::sampler::Sampler;
+
+use super::{perlin::Perlin, texture::Texture};
+
+// Octaves of the turbulence used by the patterns
+const TURBULENCE_DEPTH: usize = 7;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum NoisePattern {
+    // Plain smooth Perlin noise
+    Noise,
+    // Several octaves of noise, cloudy
+    Turbulence,
+    // Stripes along z disturbed by the turbulence
+    Marble,
+    // Rings around the y axis disturbed by the turbulence
+    Wood,
+}
+
+impl NoisePattern {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "noise" => Some(NoisePattern::Noise),
+            "turbulence" => Some(NoisePattern::Turbulence),
+            "marble" => Some(NoisePattern::Marble),
+            "wood" => Some(NoisePattern::Wood),
+            _ => None,
+        }
+    }
+}
+
+// Procedural texture evaluated from the hit point, mixing two colors with a Perlin noise pattern.
+// `scale` is the frequency of the pattern, larger values give smaller details.
+pub struct NoiseTexture {
+    pub pattern: NoisePattern,
+    pub scale: f64,
+    pub dark: Color,
+    pub light: Color,
+    noise: Perlin,
+}
+
+impl NoiseTexture {
+    pub fn new(pattern: NoisePattern, scale: f64, sampler: &mut Sampler) -> Self {
+        Self::with_colors(pattern, scale, Color::ZERO, Color::ONE, sampler)
+    }
+
+    pub fn with_colors(pattern: NoisePattern, scale: f64, dark: Color, light: Color, sampler: &mut Sampler) -> Self {
+        Self {
+            pattern,
+            scale,
+            dark,
+            light,
+            noise: Perlin::new(sampler),
+        }
+    }
+}
+
+impl Texture for NoiseTexture {
+    fn value(&self, _u: f64, _v: f64, point: Point) -> Color {
+        let t: f64 = match self.pattern {
+            NoisePattern::Noise => 0.5 * (1.0 + self.noise.noise(self.scale * point)),
+            NoisePattern::Turbulence => self.noise.turbulence(self.scale * point, TURBULENCE_DEPTH),
+            NoisePattern::Marble => {
+                let turbulence: f64 = self.noise.turbulence(point, TURBULENCE_DEPTH);
+                0.5 * (1.0 + (self.scale * point.z + 10.0 * turbulence).sin())
+            }
+            NoisePattern::Wood => {
+                let turbulence: f64 = self.noise.turbulence(point, TURBULENCE_DEPTH);
+                (self.scale * point.x.hypot(point.z) + 2.0 * turbulence).fract()
+            }
+        };
+        self.dark.lerp(self.light, t.clamp(0.0, 1.0))
+    }
+}
@@ -0,0 +1,92 @@
+use glam::{DVec3, DVec3 as Point};
+
+use crate::utils::{
+    math::{random_int, random_unit_vector},
+    sampler::Sampler,
+};
+
+const POINT_COUNT: usize = 256;
+
+// Gradient noise: a random unit vector sits on every integer lattice point and the noise
+// interpolates their dot products with the offsets to the point, so it's smooth and in -1..1.
+pub struct Perlin {
+    random_vectors: Vec<DVec3>,
+    perm_x: Vec<usize>,
+    perm_y: Vec<usize>,
+    perm_z: Vec<usize>,
+}
+
+impl Perlin {
+    pub fn new(sampler: &mut Sampler) -> Self {
+        let random_vectors: Vec<DVec3> = (0..POINT_COUNT).map(|_| random_unit_vector(sampler)).collect();
+        Self {
+            random_vectors,
+            perm_x: generate_perm(sampler),
+            perm_y: generate_perm(sampler),
+            perm_z: generate_perm(sampler),
+        }
+    }
+
+    pub fn noise(&self, point: Point) -> f64 {
+        let floor: Point = point.floor();
+        let fraction: DVec3 = point - floor;
+        let i: i64 = floor.x as i64;
+        let j: i64 = floor.y as i64;
+        let k: i64 = floor.z as i64;
+
+        let mut corners: [[[DVec3; 2]; 2]; 2] = [[[DVec3::ZERO; 2]; 2]; 2];
+        for (di, plane) in corners.iter_mut().enumerate() {
+            for (dj, row) in plane.iter_mut().enumerate() {
+                for (dk, corner) in row.iter_mut().enumerate() {
+                    let index: usize = self.perm_x[((i + di as i64) & 255) as usize]
+                        ^ self.perm_y[((j + dj as i64) & 255) as usize]
+                        ^ self.perm_z[((k + dk as i64) & 255) as usize];
+                    *corner = self.random_vectors[index];
+                }
+            }
+        }
+        perlin_interpolation(&corners, fraction)
+    }
+
+    // Sum of `depth` octaves of the noise, each one twice the frequency and half the weight of the previous one
+    pub fn turbulence(&self, point: Point, depth: usize) -> f64 {
+        let mut accumulated: f64 = 0.0;
+        let mut temp_point: Point = point;
+        let mut weight: f64 = 1.0;
+        for _ in 0..depth {
+            accumulated += weight * self.noise(temp_point);
+            weight *= 0.5;
+            temp_point *= 2.0;
+        }
+        accumulated.abs()
+    }
+}
+
+fn generate_perm(sampler: &mut Sampler) -> Vec<usize> {
+    let mut perm: Vec<usize> = (0..POINT_COUNT).collect();
+    // Fisher-Yates shuffle
+    for i in (1..POINT_COUNT).rev() {
+        let target: usize = random_int(sampler, 0.0, i as f64) as usize;
+        perm.swap(i, target.min(i));
+    }
+    perm
+}
+
+fn perlin_interpolation(corners: &[[[DVec3; 2]; 2]; 2], fraction: DVec3) -> f64 {
+    // Hermite smoothing, so the noise has no visible grid artifacts
+    let smooth: DVec3 = fraction * fraction * (3.0 - 2.0 * fraction);
+    let mut accumulated: f64 = 0.0;
+    for (i, plane) in corners.iter().enumerate() {
+        for (j, row) in plane.iter().enumerate() {
+            for (k, corner) in row.iter().enumerate() {
+                let (fi, fj, fk): (f64, f64, f64) = (i as f64, j as f64, k as f64);
+                let weight: DVec3 = fraction - DVec3::new(fi, fj, fk);
+                accumulated += (fi * smooth.x + (1.0 - fi) * (1.0 - smooth.x))
+                    * (fj * smooth.y + (1.0 - fj) * (1.0 - smooth.y))
+                    * (fk * smooth.z + (1.0 - fk) * (1.0 - smooth.z))
+                    * corner.dot(weight);
+            }
+        }
+    }
+    accumulated
+}
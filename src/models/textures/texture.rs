@@ -0,0 +1,22 @@
+use glam::{DVec3 as Color, DVec3 as Point};
+
+// Color of a surface at a hit, from its texture coordinates or its position
+pub trait Texture: Send + Sync {
+    fn value(&self, u: f64, v: f64, point: Point) -> Color;
+}
+
+pub struct SolidColor {
+    pub albedo: Color,
+}
+
+impl SolidColor {
+    pub fn new(albedo: Color) -> Self {
+        Self { albedo }
+    }
+}
+
+impl Texture for SolidColor {
+    fn value(&self, _u: f64, _v: f64, _point: Point) -> Color {
+        self.albedo
+    }
+}
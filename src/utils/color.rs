@@ -2,7 +2,7 @@ use std::ops::Range;
 
 use glam::DVec3 as Color;
 
-pub fn convert_to_rgb(color:Color) {
+pub fn convert_to_rgb(color:Color) -> [u8; 3] {
     let r:f64 = linear_to_gamma(color.x);
     let g:f64 = linear_to_gamma(color.y);
     let b:f64 = linear_to_gamma(color.z);
@@ -11,13 +11,37 @@ pub fn convert_to_rgb(color:Color) {
     let ir: u8 = (255.999 * r.clamp(intensity.start, intensity.end)) as u8;
     let ig: u8 = (255.999 * g.clamp(intensity.start, intensity.end)) as u8;
     let ib: u8 = (255.999 * b.clamp(intensity.start, intensity.end)) as u8;
-    
-    println!("{} {} {}", ir, ig, ib);
+
+    [ir, ig, ib]
 }
 
 fn linear_to_gamma(linear_coponent:f64) -> f64 {
     if linear_coponent > 0.0 {
         return linear_coponent.sqrt();
     };
-    return 0.0
-}
\ No newline at end of file
+    0.0
+}
+
+// sRGB transfer function, clamped to 0..1
+pub fn linear_to_srgb(linear_component: f64) -> f64 {
+    let linear: f64 = linear_component.clamp(0.0, 1.0);
+    if linear <= 0.0031308 {
+        12.92 * linear
+    } else {
+        1.055 * linear.powf(1.0 / 2.4) - 0.055
+    }
+}
+
+// Inverse of linear_to_srgb
+pub fn srgb_to_linear(srgb_component: f64) -> f64 {
+    if srgb_component <= 0.04045 {
+        srgb_component / 12.92
+    } else {
+        ((srgb_component + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+// Inverse of the gamma used by convert_to_rgb
+pub fn gamma_to_linear(gamma_component: f64) -> f64 {
+    gamma_component * gamma_component
+}
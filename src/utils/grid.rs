@@ -0,0 +1,46 @@
+use std::{fs, io, path::Path};
+
+use crate::models::objects::grid_volume::DensityGrid;
+
+use super::ppm::invalid_data;
+
+// Loads a raw grid of densities, without any header: the values follow each other x first, then y,
+// then z, either as bytes (0 to 255 giving 0.0 to 1.0) or as little endian f32, told apart by the file size.
+pub fn load_raw_grid(path: &Path, resolution: [usize; 3]) -> io::Result<DensityGrid> {
+    let data: Vec<u8> = fs::read(path)
+        .map_err(|error| io::Error::new(error.kind(), format!("{}: {}", path.display(), error)))?;
+
+    let count: usize = resolution
+        .iter()
+        .try_fold(1_usize, |count, &size| count.checked_mul(size))
+        .ok_or_else(|| {
+            invalid_data(format!(
+                "{}: grid size {}x{}x{} is too large",
+                path.display(),
+                resolution[0],
+                resolution[1],
+                resolution[2]
+            ))
+        })?;
+    let values: Vec<f64> = if data.len() == count {
+        data.iter().map(|&byte| byte as f64 / 255.0).collect()
+    } else if count.checked_mul(4) == Some(data.len()) {
+        data.chunks_exact(4)
+            .map(|bytes| f32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]) as f64)
+            .collect()
+    } else {
+        return Err(invalid_data(format!(
+            "{}: {} bytes don't fit a {}x{}x{} grid of bytes or f32",
+            path.display(),
+            data.len(),
+            resolution[0],
+            resolution[1],
+            resolution[2]
+        )));
+    };
+
+    if let Some(index) = values.iter().position(|value| !value.is_finite()) {
+        return Err(invalid_data(format!("{}: grid value {} is not finite", path.display(), index)));
+    }
+    Ok(DensityGrid::new(resolution, values))
+}
@@ -0,0 +1,110 @@
+use std::io::{self, Write};
+
+use glam::DVec3 as Color;
+
+use crate::models::image::Image;
+
+// Radiance RGBE file, keeps the full linear radiance (no clamping, no gamma)
+pub fn write_hdr(image: &Image, out: &mut impl Write) -> io::Result<()> {
+    writeln!(out, "#?RADIANCE")?;
+    writeln!(out, "FORMAT=32-bit_rle_rgbe")?;
+    writeln!(out)?;
+    writeln!(out, "-Y {} +X {}", image.height, image.width)?;
+
+    for row in image.rows() {
+        let scanline: Vec<[u8; 4]> = row.iter().map(|pixel| color_to_rgbe(*pixel)).collect();
+        write_scanline(&scanline, out)?;
+    }
+    out.flush()
+}
+
+// Shared exponent encoding: the three channels keep 8 bits of mantissa relative to the brightest one
+pub fn color_to_rgbe(color: Color) -> [u8; 4] {
+    let color: Color = color.max(Color::ZERO);
+    let brightest: f64 = color.max_element();
+    if brightest < 1e-32 || !brightest.is_finite() {
+        return [0, 0, 0, 0];
+    }
+
+    // brightest = mantissa * 2^exponent with mantissa in 0.5..1
+    let mut exponent: i32 = brightest.log2().floor() as i32 + 1;
+    if brightest / 2f64.powi(exponent) >= 1.0 {
+        exponent += 1;
+    }
+    let scale: f64 = 256.0 / 2f64.powi(exponent);
+    [
+        (color.x * scale) as u8,
+        (color.y * scale) as u8,
+        (color.z * scale) as u8,
+        (exponent + 128).clamp(0, 255) as u8,
+    ]
+}
+
+pub fn rgbe_to_color(rgbe: [u8; 4]) -> Color {
+    if rgbe[3] == 0 {
+        return Color::ZERO;
+    }
+    let scale: f64 = 2f64.powi(rgbe[3] as i32 - (128 + 8));
+    Color::new(
+        (rgbe[0] as f64 + 0.5) * scale,
+        (rgbe[1] as f64 + 0.5) * scale,
+        (rgbe[2] as f64 + 0.5) * scale,
+    )
+}
+
+// Run length encoded scanline, each channel is encoded separately. Readers only accept it
+// for widths between 8 and 32767, other widths are written flat.
+fn write_scanline(scanline: &[[u8; 4]], out: &mut impl Write) -> io::Result<()> {
+    let width: usize = scanline.len();
+    if !(8..0x8000).contains(&width) {
+        for pixel in scanline {
+            out.write_all(pixel)?;
+        }
+        return Ok(());
+    }
+
+    out.write_all(&[2, 2, (width >> 8) as u8, (width & 0xFF) as u8])?;
+    for channel in 0..4 {
+        let bytes: Vec<u8> = scanline.iter().map(|pixel| pixel[channel]).collect();
+        write_rle_channel(&bytes, out)?;
+    }
+    Ok(())
+}
+
+fn write_rle_channel(bytes: &[u8], out: &mut impl Write) -> io::Result<()> {
+    const MIN_RUN: usize = 4;
+    let mut current: usize = 0;
+
+    while current < bytes.len() {
+        // Looking for the next run long enough to be worth encoding
+        let mut run_start: usize = current;
+        let mut run_length: usize = 0;
+        while run_start < bytes.len() {
+            run_length = 1;
+            while run_start + run_length < bytes.len()
+                && run_length < 127
+                && bytes[run_start + run_length] == bytes[run_start]
+            {
+                run_length += 1;
+            }
+            if run_length >= MIN_RUN {
+                break;
+            }
+            run_start += run_length;
+        }
+
+        // Everything before the run is written as literal bytes, at most 128 at a time
+        while current < run_start {
+            let count: usize = (run_start - current).min(128);
+            out.write_all(&[count as u8])?;
+            out.write_all(&bytes[current..current + count])?;
+            current += count;
+        }
+
+        if run_length >= MIN_RUN && run_start < bytes.len() {
+            out.write_all(&[128 + run_length as u8, bytes[run_start]])?;
+            current = run_start + run_length;
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,34 @@
+use std::{fs, io, path::Path, sync::Arc};
+
+use crate::models::{image::Image, materials::material::Material, objects::mesh::Mesh};
+
+use super::{obj::load_obj, pfm::read_pfm, ply::load_ply, png::read_png, ppm::{invalid_data, read_ppm}, stl::load_stl};
+
+// Loads a previous render or a texture, the format is found from the magic number at the start of the file
+pub fn load_image(path: &Path) -> io::Result<Image> {
+    let located = |error: io::Error| io::Error::new(error.kind(), format!("{}: {}", path.display(), error));
+    let data: Vec<u8> = fs::read(path).map_err(located)?;
+
+    match data.get(0..2) {
+        Some(b"P3") | Some(b"P6") => read_ppm(&mut data.as_slice()),
+        Some(b"PF") | Some(b"Pf") => read_pfm(&mut data.as_slice()),
+        _ if data.starts_with(b"\x89PNG") => read_png(&data),
+        _ => Err(invalid_data("unknown image format, expected PPM, PNG or PFM".to_string())),
+    }
+    .map_err(located)
+}
+
+// Loads an OBJ, PLY or STL mesh, picked from the file extension
+pub fn load_mesh(path: &Path, material: Arc<dyn Material>) -> io::Result<Mesh> {
+    let extension: String = path
+        .extension()
+        .map(|extension| extension.to_string_lossy().to_lowercase())
+        .unwrap_or_default();
+
+    match extension.as_str() {
+        "obj" => load_obj(path, material),
+        "ply" => load_ply(path, material),
+        "stl" => load_stl(path, material),
+        _ => Err(invalid_data(format!("unknown mesh format for {}, expected .obj, .ply or .stl", path.display()))),
+    }
+}
@@ -1,25 +1,26 @@
 use std::{f64::consts::PI, ops::Range};
 
 use glam::DVec3;
-use rand::Rng;
 
-pub fn random_float() -> f64 {
-    rand::thread_rng().gen_range(0.0..1.0)
+use super::sampler::Sampler;
+
+pub fn random_float(sampler: &mut Sampler) -> f64 {
+    sampler.random_float()
 }
 
-pub fn random(min: f64, max: f64) -> f64 {
-    min + (max - min) * random_float()
+pub fn random(sampler: &mut Sampler, min: f64, max: f64) -> f64 {
+    min + (max - min) * random_float(sampler)
 }
 
-pub fn random_float_vector() -> DVec3 {
-    DVec3::new(random_float(), random_float(), random_float())
+pub fn random_float_vector(sampler: &mut Sampler) -> DVec3 {
+    DVec3::new(random_float(sampler), random_float(sampler), random_float(sampler))
 }
-pub fn random_vector(min: f64, max: f64) -> DVec3 {
-    DVec3::new(random(min, max), random(min, max), random(min, max))
+pub fn random_vector(sampler: &mut Sampler, min: f64, max: f64) -> DVec3 {
+    DVec3::new(random(sampler, min, max), random(sampler, min, max), random(sampler, min, max))
 }
-pub fn random_unit_vector() -> DVec3 {
+pub fn random_unit_vector(sampler: &mut Sampler) -> DVec3 {
     loop {
-        let p: DVec3 = random_vector(-1.0, 1.0);
+        let p: DVec3 = random_vector(sampler, -1.0, 1.0);
         let lensq: f64 = p.length_squared();
         if 1e-160 < lensq && lensq <= 1.0 {
             return p / lensq.sqrt();
@@ -27,6 +28,32 @@ pub fn random_unit_vector() -> DVec3 {
     }
 }
 
+// Direction around the z axis with a density proportional to its cosine with it, cos(theta) / PI
+pub fn random_cosine_direction(sampler: &mut Sampler) -> DVec3 {
+    let r1: f64 = random_float(sampler);
+    let r2: f64 = random_float(sampler);
+    let phi: f64 = 2.0 * PI * r1;
+    DVec3::new(phi.cos() * r2.sqrt(), phi.sin() * r2.sqrt(), (1.0 - r2).sqrt())
+}
+
+// Weight of a sample drawn with density `pdf` when another strategy could also have drawn it with `other_pdf`,
+// the two weights of a sample always sum to 1
+pub fn power_heuristic(pdf: f64, other_pdf: f64) -> f64 {
+    let (pdf, other_pdf): (f64, f64) = (pdf * pdf, other_pdf * other_pdf);
+    if pdf + other_pdf <= 0.0 {
+        return 0.0;
+    }
+    pdf / (pdf + other_pdf)
+}
+
+// Uniform direction in the cone of half angle acos(cos_theta_max) around the z axis
+pub fn random_in_cone(cos_theta_max: f64, sampler: &mut Sampler) -> DVec3 {
+    let z: f64 = 1.0 + random_float(sampler) * (cos_theta_max - 1.0);
+    let phi: f64 = 2.0 * PI * random_float(sampler);
+    let sin_theta: f64 = (1.0 - z * z).max(0.0).sqrt();
+    DVec3::new(phi.cos() * sin_theta, phi.sin() * sin_theta, z)
+}
+
 pub fn near_zero(vec: DVec3) -> bool {
     let s: f64 = 1.0e-8;
     vec[0].abs() < s && vec[1].abs() < s && vec[2].abs() < s
@@ -53,9 +80,9 @@ pub fn degrees_to_radians(degree: f64) -> f64 {
     degree * PI / 180.0
 }
 
-pub fn random_in_unit_disk() -> DVec3 {
+pub fn random_in_unit_disk(sampler: &mut Sampler) -> DVec3 {
     loop {
-        let p: DVec3 = DVec3::new(random(-1.0, 1.0), random(-1.0, 1.0), 0.0);
+        let p: DVec3 = DVec3::new(random(sampler, -1.0, 1.0), random(sampler, -1.0, 1.0), 0.0);
         if p.length_squared() < 1.0 {
             return p;
         }
@@ -73,6 +100,6 @@ pub fn new_interval(a: Range<f64>, b: Range<f64>) -> Range<f64> {
     min..max
 }
 
-pub fn random_int(min: f64, max: f64) -> i32 {
-    return random(min, max+1.0) as i32;
+pub fn random_int(sampler: &mut Sampler, min: f64, max: f64) -> i32 {
+    random(sampler, min, max+1.0) as i32
 }
\ No newline at end of file
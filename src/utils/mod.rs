@@ -1,2 +1,14 @@
 pub mod color;
-pub mod math;
\ No newline at end of file
+pub mod grid;
+pub mod hdr;
+pub mod input;
+pub mod math;
+pub mod obj;
+pub mod onb;
+pub mod output;
+pub mod pfm;
+pub mod ply;
+pub mod png;
+pub mod ppm;
+pub mod sampler;
+pub mod stl;
\ No newline at end of file
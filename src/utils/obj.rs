@@ -0,0 +1,368 @@
+use std::{collections::HashMap, fs, io, path::Path, sync::Arc};
+
+use glam::{DVec2, DVec3, DVec3 as Point, DVec3 as Color};
+
+use crate::models::{
+    materials::{
+        dielectric::Dielectric,
+        diffuse_light::DiffuseLight,
+        material::{Lambertian, Material},
+        metal::Metal,
+    },
+    objects::mesh::Mesh,
+    textures::{
+        image_texture::{ImageTexture, WrapMode},
+        texture::Texture,
+    },
+};
+
+use super::ppm::invalid_data;
+
+// Loads a Wavefront OBJ file and the MTL libraries it references.
+// Polygons are split in triangles (as a fan, so they should be convex) and faces
+// without a material use `default_material`.
+pub fn load_obj(path: &Path, default_material: Arc<dyn Material>) -> io::Result<Mesh> {
+    let text: String = fs::read_to_string(path)
+        .map_err(|error| io::Error::new(error.kind(), format!("{}: {}", path.display(), error)))?;
+    read_obj(&text, path, default_material)
+}
+
+// `path` locates the errors and the MTL libraries
+fn read_obj(text: &str, path: &Path, default_material: Arc<dyn Material>) -> io::Result<Mesh> {
+    let directory: &Path = path.parent().unwrap_or(Path::new(""));
+    let located = |line: usize, error: io::Error| {
+        io::Error::new(error.kind(), format!("{}:{}: {}", path.display(), line, error))
+    };
+
+    let mut obj_positions: Vec<Point> = Vec::new();
+    let mut obj_uvs: Vec<DVec2> = Vec::new();
+    let mut obj_normals: Vec<DVec3> = Vec::new();
+
+    let mut library: HashMap<String, Arc<dyn Material>> = HashMap::new();
+    let mut materials: Vec<Arc<dyn Material>> = vec![default_material];
+    let mut material_slots: HashMap<String, u32> = HashMap::new();
+    let mut current_material: u32 = 0;
+
+    // OBJ faces index positions, uvs and normals separately, every distinct combination becomes one mesh vertex
+    let mut vertex_slots: HashMap<(usize, Option<usize>, Option<usize>), u32> = HashMap::new();
+    let mut vertices: Vec<(usize, Option<usize>, Option<usize>)> = Vec::new();
+    let mut indices: Vec<[u32; 3]> = Vec::new();
+    let mut face_materials: Vec<u32> = Vec::new();
+
+    for (index, line) in text.lines().enumerate() {
+        let line_number: usize = index + 1;
+        let content: &str = line.split('#').next().unwrap_or_default();
+        let mut words: std::str::SplitWhitespace<'_> = content.split_whitespace();
+        let Some(keyword) = words.next() else {
+            continue;
+        };
+        let arguments: Vec<&str> = words.collect();
+
+        match keyword {
+            "v" => obj_positions.push(parse_vector(&arguments, "vertex").map_err(|e| located(line_number, e))?),
+            "vn" => obj_normals.push(parse_vector(&arguments, "normal").map_err(|e| located(line_number, e))?),
+            "vt" => {
+                let uv: Vec<f64> = parse_numbers(&arguments, 1, 3, "texture coordinate")
+                    .map_err(|e| located(line_number, e))?;
+                obj_uvs.push(DVec2::new(uv[0], uv.get(1).copied().unwrap_or(0.0)));
+            }
+            "f" => {
+                if arguments.len() < 3 {
+                    return Err(located(line_number, invalid_data("a face needs at least 3 vertices".to_string())));
+                }
+                let mut polygon: Vec<u32> = Vec::with_capacity(arguments.len());
+                for corner in arguments.iter() {
+                    let key: (usize, Option<usize>, Option<usize>) = parse_corner(
+                        corner,
+                        obj_positions.len(),
+                        obj_uvs.len(),
+                        obj_normals.len(),
+                    )
+                    .map_err(|e| located(line_number, e))?;
+                    let slot: u32 = *vertex_slots.entry(key).or_insert_with(|| {
+                        vertices.push(key);
+                        (vertices.len() - 1) as u32
+                    });
+                    polygon.push(slot);
+                }
+                for i in 1..polygon.len() - 1 {
+                    indices.push([polygon[0], polygon[i], polygon[i + 1]]);
+                    face_materials.push(current_material);
+                }
+            }
+            "mtllib" => {
+                for file in arguments.iter() {
+                    let mtl_path: std::path::PathBuf = directory.join(file);
+                    library.extend(load_mtl(&mtl_path).map_err(|e| located(line_number, e))?);
+                }
+            }
+            "usemtl" => {
+                let name: String = arguments.join(" ");
+                // Unknown materials (often "None" or a missing library) fall back to the default one
+                current_material = match library.get(&name) {
+                    Some(material) => *material_slots.entry(name).or_insert_with(|| {
+                        materials.push(material.clone());
+                        (materials.len() - 1) as u32
+                    }),
+                    None => 0,
+                };
+            }
+            // Groups, objects, smoothing groups, lines and the rest don't change the triangles
+            _ => {}
+        }
+    }
+
+    let has_uvs: bool = vertices.iter().any(|(_, uv, _)| uv.is_some());
+    let has_normals: bool = vertices.iter().any(|(_, _, normal)| normal.is_some());
+
+    let positions: Vec<Point> = vertices.iter().map(|(position, _, _)| obj_positions[*position]).collect();
+    let uvs: Vec<DVec2> = if has_uvs {
+        vertices.iter().map(|(_, uv, _)| uv.map_or(DVec2::ZERO, |uv| obj_uvs[uv])).collect()
+    } else {
+        Vec::new()
+    };
+    let normals: Vec<DVec3> = if has_normals {
+        vertices
+            .iter()
+            .map(|(_, _, normal)| normal.map_or(DVec3::ZERO, |normal| obj_normals[normal].normalize_or_zero()))
+            .collect()
+    } else {
+        Vec::new()
+    };
+
+    Ok(Mesh::new(positions, normals, uvs, Vec::new(), indices, face_materials, materials))
+}
+
+// Reads a face corner "v", "v/vt", "v//vn" or "v/vt/vn", indexes start at 1 and negative ones count from the end
+fn parse_corner(
+    corner: &str,
+    position_count: usize,
+    uv_count: usize,
+    normal_count: usize,
+) -> io::Result<(usize, Option<usize>, Option<usize>)> {
+    let mut parts: std::str::Split<'_, char> = corner.split('/');
+    let position: usize = match parts.next() {
+        Some(part) if !part.is_empty() => resolve_index(part, position_count, "vertex")?,
+        _ => return Err(invalid_data(format!("face corner {:?} has no vertex", corner))),
+    };
+    let uv: Option<usize> = match parts.next() {
+        Some(part) if !part.is_empty() => Some(resolve_index(part, uv_count, "texture coordinate")?),
+        _ => None,
+    };
+    let normal: Option<usize> = match parts.next() {
+        Some(part) if !part.is_empty() => Some(resolve_index(part, normal_count, "normal")?),
+        _ => None,
+    };
+    if parts.next().is_some() {
+        return Err(invalid_data(format!("malformed face corner {:?}", corner)));
+    }
+    Ok((position, uv, normal))
+}
+
+fn resolve_index(part: &str, count: usize, name: &str) -> io::Result<usize> {
+    let index: i64 = part
+        .parse::<i64>()
+        .map_err(|_| invalid_data(format!("invalid {} index {:?}", name, part)))?;
+    let resolved: i64 = if index < 0 { count as i64 + index } else { index - 1 };
+    if index == 0 || resolved < 0 || resolved >= count as i64 {
+        return Err(invalid_data(format!(
+            "{} index {} out of range, {} defined so far",
+            name, index, count
+        )));
+    }
+    Ok(resolved as usize)
+}
+
+fn parse_numbers(arguments: &[&str], min: usize, max: usize, name: &str) -> io::Result<Vec<f64>> {
+    if arguments.len() < min || arguments.len() > max {
+        return Err(invalid_data(format!(
+            "{} expects {} to {} numbers, got {}",
+            name,
+            min,
+            max,
+            arguments.len()
+        )));
+    }
+    arguments
+        .iter()
+        .map(|argument| {
+            argument
+                .parse::<f64>()
+                .ok()
+                .filter(|number| number.is_finite())
+                .ok_or_else(|| invalid_data(format!("invalid {} value {:?}", name, argument)))
+        })
+        .collect()
+}
+
+// Three numbers, OBJ allows an extra fourth one (the w of a vertex) which is ignored
+fn parse_vector(arguments: &[&str], name: &str) -> io::Result<DVec3> {
+    let numbers: Vec<f64> = parse_numbers(arguments, 3, 4, name)?;
+    Ok(DVec3::new(numbers[0], numbers[1], numbers[2]))
+}
+
+struct MtlMaterial {
+    diffuse: Color,
+    specular: Color,
+    emission: Color,
+    shininess: f64,
+    refraction_index: f64,
+    dissolve: f64,
+    illumination: u32,
+    // Image of map_Kd, it replaces the diffuse color
+    diffuse_map: Option<Arc<dyn Texture>>,
+}
+
+impl Default for MtlMaterial {
+    fn default() -> Self {
+        Self {
+            diffuse: Color::new(0.8, 0.8, 0.8),
+            specular: Color::ZERO,
+            emission: Color::ZERO,
+            shininess: 0.0,
+            refraction_index: 1.5,
+            dissolve: 1.0,
+            illumination: 2,
+            diffuse_map: None,
+        }
+    }
+}
+
+impl MtlMaterial {
+    // Picks the closest of our materials:
+    // - an emissive color (Ke) gives a DiffuseLight
+    // - transparency (d < 1, Tr > 0 or a refraction illumination model) gives a Dielectric with Ni
+    // - a reflection illumination model (3, 5, 8), or only a specular color, gives a Metal,
+    //   its fuzziness gets larger as the specular exponent (Ns) gets smaller
+    // - anything else is a Lambertian of the diffuse color (Kd), or of the diffuse image (map_Kd)
+    fn to_material(&self) -> Arc<dyn Material> {
+        if self.emission.max_element() > 0.0 {
+            return Arc::new(DiffuseLight::new(self.emission));
+        }
+        if self.dissolve < 1.0 || matches!(self.illumination, 4 | 6 | 7 | 9) {
+            return Arc::new(Dielectric::new(self.refraction_index));
+        }
+        let reflective: bool = matches!(self.illumination, 3 | 5 | 8);
+        if reflective || (self.specular.max_element() > 0.0 && self.diffuse.max_element() <= 0.0) {
+            let albedo: Color = if self.specular.max_element() > 0.0 {
+                self.specular
+            } else {
+                self.diffuse
+            };
+            let fuzziness: f64 = (2.0 / (self.shininess.max(0.0) + 2.0)).sqrt();
+            return Arc::new(Metal::new(albedo, fuzziness));
+        }
+        match &self.diffuse_map {
+            Some(texture) => Arc::new(Lambertian::from_texture(texture.clone())),
+            None => Arc::new(Lambertian::new(self.diffuse)),
+        }
+    }
+}
+
+fn load_mtl(path: &Path) -> io::Result<HashMap<String, Arc<dyn Material>>> {
+    let directory: &Path = path.parent().unwrap_or(Path::new(""));
+    let text: String = fs::read_to_string(path)
+        .map_err(|error| io::Error::new(error.kind(), format!("{}: {}", path.display(), error)))?;
+    let located = |line: usize, error: io::Error| {
+        io::Error::new(error.kind(), format!("{}:{}: {}", path.display(), line, error))
+    };
+
+    let mut definitions: Vec<(String, MtlMaterial)> = Vec::new();
+    for (index, line) in text.lines().enumerate() {
+        let line_number: usize = index + 1;
+        let content: &str = line.split('#').next().unwrap_or_default();
+        let mut words: std::str::SplitWhitespace<'_> = content.split_whitespace();
+        let Some(keyword) = words.next() else {
+            continue;
+        };
+        let arguments: Vec<&str> = words.collect();
+
+        if keyword == "newmtl" {
+            definitions.push((arguments.join(" "), MtlMaterial::default()));
+            continue;
+        }
+        let Some((_, current)) = definitions.last_mut() else {
+            return Err(located(line_number, invalid_data(format!("{} before any newmtl", keyword))));
+        };
+
+        let number = |name: &str| -> io::Result<f64> {
+            parse_numbers(&arguments, 1, 1, name).map(|numbers| numbers[0])
+        };
+        let result: io::Result<()> = match keyword {
+            "Kd" => parse_vector(&arguments, "Kd").map(|color| current.diffuse = color),
+            "Ks" => parse_vector(&arguments, "Ks").map(|color| current.specular = color),
+            "Ke" => parse_vector(&arguments, "Ke").map(|color| current.emission = color),
+            "Ns" => number("Ns").map(|value| current.shininess = value),
+            "Ni" => number("Ni").map(|value| current.refraction_index = value),
+            "d" => number("d").map(|value| current.dissolve = value),
+            "Tr" => number("Tr").map(|value| current.dissolve = 1.0 - value),
+            "illum" => match arguments[..] {
+                [model] => model
+                    .parse::<u32>()
+                    .map(|model| current.illumination = model)
+                    .map_err(|_| invalid_data(format!("invalid illum value {:?}", model))),
+                _ => Err(invalid_data("illum expects a single number".to_string())),
+            },
+            "map_Kd" => match arguments.last() {
+                // Options come before the file name, only "-clamp on" is used
+                Some(file) => {
+                    let clamp: bool = arguments.windows(2).any(|pair| pair == ["-clamp", "on"]);
+                    let wrap: WrapMode = if clamp { WrapMode::Clamp } else { WrapMode::Repeat };
+                    ImageTexture::load(&directory.join(file), wrap).map(|texture| {
+                        current.diffuse_map = Some(Arc::new(texture));
+                    })
+                }
+                None => Err(invalid_data("map_Kd expects an image file".to_string())),
+            },
+            // The other maps and statements don't map to our materials
+            _ => Ok(()),
+        };
+        result.map_err(|e| located(line_number, e))?;
+    }
+
+    Ok(definitions
+        .into_iter()
+        .map(|(name, definition)| (name, definition.to_material()))
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(text: &str) -> io::Result<Mesh> {
+        read_obj(text, Path::new("test.obj"), Arc::new(Lambertian::new(Color::ONE)))
+    }
+
+    fn message(text: &str) -> String {
+        parse(text).err().expect("the OBJ should be rejected").to_string()
+    }
+
+    #[test]
+    fn polygons_are_split_in_triangles() {
+        let mesh: Mesh = parse("v 0 0 0\nv 1 0 0\nv 1 1 0\nv 0 1 0\nf 1 2 3 4\nf -4 -3 -2\n").unwrap();
+        assert_eq!(mesh.triangle_count(), 3);
+    }
+
+    #[test]
+    fn out_of_range_index() {
+        assert_eq!(
+            message("v 0 0 0\nv 1 0 0\nv 1 1 0\nf 1 2 4\n"),
+            "test.obj:4: vertex index 4 out of range, 3 defined so far"
+        );
+    }
+
+    #[test]
+    fn negative_index_before_the_start() {
+        assert_eq!(
+            message("v 0 0 0\nv 1 0 0\nv 1 1 0\nf -1 -2 -4\n"),
+            "test.obj:4: vertex index -4 out of range, 3 defined so far"
+        );
+    }
+
+    #[test]
+    fn bad_texture_coordinate() {
+        assert_eq!(message("v 0 0 0\nvt 0.5 nan\n"), "test.obj:2: invalid texture coordinate value \"nan\"");
+        assert_eq!(message("vt\n"), "test.obj:1: texture coordinate expects 1 to 3 numbers, got 0");
+    }
+}
@@ -0,0 +1,25 @@
+use glam::DVec3;
+
+// Orthonormal basis built around a direction, `w` being that direction.
+// Directions sampled around the z axis are turned to be around `w` with `transform`.
+pub struct Onb {
+    pub u: DVec3,
+    pub v: DVec3,
+    pub w: DVec3,
+}
+
+impl Onb {
+    pub fn new(direction: DVec3) -> Self {
+        let w: DVec3 = direction.normalize();
+        // Any vector not parallel to w gives the two others
+        let helper: DVec3 = if w.x.abs() > 0.9 { DVec3::Y } else { DVec3::X };
+        let v: DVec3 = w.cross(helper).normalize();
+        let u: DVec3 = w.cross(v);
+        Self { u, v, w }
+    }
+
+    // From coordinates in the basis to 'world' ones
+    pub fn transform(&self, local: DVec3) -> DVec3 {
+        local.x * self.u + local.y * self.v + local.z * self.w
+    }
+}
@@ -0,0 +1,77 @@
+use std::{
+    fs::File,
+    io::{self, BufWriter, Write},
+    path::Path,
+};
+
+use crate::models::image::Image;
+
+use super::{
+    hdr::write_hdr,
+    pfm::write_pfm,
+    png::write_png,
+    ppm::{write_p3, write_p6},
+};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ImageFormat {
+    P3,
+    P6,
+    Png,
+    Png16,
+    Hdr,
+    Pfm,
+}
+
+impl ImageFormat {
+    // Guesses the format from the file extension, PPM files are written in binary and PNG files with 8 bits per channel
+    pub fn from_path(path: &Path) -> Option<Self> {
+        let extension: String = path.extension()?.to_str()?.to_ascii_lowercase();
+        match extension.as_str() {
+            "ppm" => Some(ImageFormat::P6),
+            "png" => Some(ImageFormat::Png),
+            "hdr" => Some(ImageFormat::Hdr),
+            "pfm" => Some(ImageFormat::Pfm),
+            _ => None,
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_ascii_lowercase().as_str() {
+            "p3" => Some(ImageFormat::P3),
+            "p6" | "ppm" => Some(ImageFormat::P6),
+            "png" => Some(ImageFormat::Png),
+            "png16" => Some(ImageFormat::Png16),
+            "hdr" => Some(ImageFormat::Hdr),
+            "pfm" => Some(ImageFormat::Pfm),
+            _ => None,
+        }
+    }
+}
+
+pub fn write_image(image: &Image, format: ImageFormat, out: &mut impl Write) -> io::Result<()> {
+    match format {
+        ImageFormat::P3 => write_p3(image, out),
+        ImageFormat::P6 => write_p6(image, out),
+        ImageFormat::Png => write_png(image, out, false),
+        ImageFormat::Png16 => write_png(image, out, true),
+        ImageFormat::Hdr => write_hdr(image, out),
+        ImageFormat::Pfm => write_pfm(image, out),
+    }
+}
+
+pub fn save_image_as(image: &Image, path: &Path, format: ImageFormat) -> io::Result<()> {
+    let mut out: BufWriter<File> = BufWriter::new(File::create(path)?);
+    write_image(image, format, &mut out)?;
+    out.flush()
+}
+
+pub fn save_image(image: &Image, path: &Path) -> io::Result<()> {
+    let format: ImageFormat = ImageFormat::from_path(path).ok_or_else(|| {
+        io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("unknown image format for {}", path.display()),
+        )
+    })?;
+    save_image_as(image, path, format)
+}
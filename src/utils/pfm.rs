@@ -0,0 +1,94 @@
+use std::io::{self, Read, Write};
+
+use glam::DVec3 as Color;
+
+use crate::models::image::Image;
+
+use super::ppm::invalid_data;
+
+// Portable Float Map, 32 bits floats per channel in little endian, rows stored from the bottom to the top
+pub fn write_pfm(image: &Image, out: &mut impl Write) -> io::Result<()> {
+    writeln!(out, "PF")?;
+    writeln!(out, "{} {}", image.width, image.height)?;
+    // A negative scale means little endian
+    writeln!(out, "-1.0")?;
+
+    for row in image.rows().rev() {
+        for pixel in row {
+            for channel in pixel.to_array() {
+                out.write_all(&(channel as f32).to_le_bytes())?;
+            }
+        }
+    }
+    out.flush()
+}
+
+// Reads a color ("PF") or grayscale ("Pf") float map, in either endianness
+pub fn read_pfm(input: &mut impl Read) -> io::Result<Image> {
+    let mut data: Vec<u8> = Vec::new();
+    input.read_to_end(&mut data)?;
+
+    // The header is made of three lines: magic, size and scale
+    let mut lines = data.splitn(4, |&byte| byte == b'\n');
+    let mut next_line = |name: &str| -> io::Result<String> {
+        lines
+            .next()
+            .map(|line| String::from_utf8_lossy(line).trim().to_string())
+            .ok_or_else(|| invalid_data(format!("PFM file is missing its {}", name)))
+    };
+
+    let channels: usize = match next_line("magic number")?.as_str() {
+        "PF" => 3,
+        "Pf" => 1,
+        magic => return Err(invalid_data(format!("unsupported PFM magic number {:?}", magic))),
+    };
+    let size: String = next_line("size")?;
+    let dimensions: Vec<usize> = size
+        .split_whitespace()
+        .map(|value| value.parse::<usize>())
+        .collect::<Result<Vec<usize>, _>>()
+        .map_err(|_| invalid_data(format!("invalid PFM size {:?}", size)))?;
+    let [width, height] = dimensions[..] else {
+        return Err(invalid_data(format!("invalid PFM size {:?}", size)));
+    };
+    let scale_line: String = next_line("scale")?;
+    let scale: f64 = scale_line
+        .parse::<f64>()
+        .map_err(|_| invalid_data(format!("invalid PFM scale {:?}", scale_line)))?;
+    let little_endian: bool = scale < 0.0;
+
+    let pixel_data: &[u8] = lines.next().unwrap_or_default();
+    let expected: usize = width
+        .checked_mul(height)
+        .and_then(|pixels| pixels.checked_mul(channels * 4))
+        .ok_or_else(|| invalid_data(format!("PFM size {}x{} is too large", width, height)))?;
+    if pixel_data.len() < expected {
+        return Err(invalid_data("PFM pixel data is truncated".to_string()));
+    }
+
+    let samples: Vec<f64> = pixel_data[..expected]
+        .chunks_exact(4)
+        .map(|bytes| {
+            let bytes: [u8; 4] = [bytes[0], bytes[1], bytes[2], bytes[3]];
+            let sample: f32 = if little_endian {
+                f32::from_le_bytes(bytes)
+            } else {
+                f32::from_be_bytes(bytes)
+            };
+            sample as f64
+        })
+        .collect();
+
+    let mut image: Image = Image::new(width, height);
+    for (index, pixel) in samples.chunks_exact(channels).enumerate() {
+        let color: Color = if channels == 3 {
+            Color::new(pixel[0], pixel[1], pixel[2])
+        } else {
+            Color::splat(pixel[0])
+        };
+        // Rows are stored bottom to top
+        let (x, y) = (index % width, height - 1 - index / width);
+        image.set(x, y, color);
+    }
+    Ok(image)
+}
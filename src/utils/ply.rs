@@ -0,0 +1,352 @@
+use std::{fs, io, path::Path, sync::Arc};
+
+use glam::{DVec2, DVec3, DVec3 as Point, DVec3 as Color};
+
+use crate::models::{materials::material::Material, objects::mesh::Mesh};
+
+use super::{color::gamma_to_linear, ppm::invalid_data};
+
+// Loads a PLY file, in ASCII or binary little endian.
+// The "vertex" element gives the positions and optionally normals (nx, ny, nz), texture
+// coordinates (u, v or s, t) and colors (red, green, blue), the "face" element gives polygons
+// which are split in triangles. Other elements and properties are skipped.
+pub fn load_ply(path: &Path, material: Arc<dyn Material>) -> io::Result<Mesh> {
+    let data: Vec<u8> = fs::read(path)
+        .map_err(|error| io::Error::new(error.kind(), format!("{}: {}", path.display(), error)))?;
+    read_ply(&data, material).map_err(|error| io::Error::new(error.kind(), format!("{}: {}", path.display(), error)))
+}
+
+#[derive(Clone, Copy)]
+enum Scalar {
+    Int8,
+    UInt8,
+    Int16,
+    UInt16,
+    Int32,
+    UInt32,
+    Float32,
+    Float64,
+}
+
+impl Scalar {
+    fn from_name(name: &str) -> io::Result<Self> {
+        match name {
+            "char" | "int8" => Ok(Scalar::Int8),
+            "uchar" | "uint8" => Ok(Scalar::UInt8),
+            "short" | "int16" => Ok(Scalar::Int16),
+            "ushort" | "uint16" => Ok(Scalar::UInt16),
+            "int" | "int32" => Ok(Scalar::Int32),
+            "uint" | "uint32" => Ok(Scalar::UInt32),
+            "float" | "float32" => Ok(Scalar::Float32),
+            "double" | "float64" => Ok(Scalar::Float64),
+            _ => Err(invalid_data(format!("unknown PLY property type {:?}", name))),
+        }
+    }
+
+    fn size(self) -> usize {
+        match self {
+            Scalar::Int8 | Scalar::UInt8 => 1,
+            Scalar::Int16 | Scalar::UInt16 => 2,
+            Scalar::Int32 | Scalar::UInt32 | Scalar::Float32 => 4,
+            Scalar::Float64 => 8,
+        }
+    }
+
+    fn is_integer(self) -> bool {
+        !matches!(self, Scalar::Float32 | Scalar::Float64)
+    }
+}
+
+struct Property {
+    name: String,
+    scalar: Scalar,
+    // Type of the item count for list properties
+    count: Option<Scalar>,
+}
+
+struct Element {
+    name: String,
+    count: usize,
+    properties: Vec<Property>,
+}
+
+// Values of the body, read either from whitespace separated text or from little endian bytes
+enum Body<'a> {
+    Ascii { words: std::str::SplitAsciiWhitespace<'a> },
+    Binary { data: &'a [u8], position: usize },
+}
+
+impl Body<'_> {
+    fn value(&mut self, scalar: Scalar) -> io::Result<f64> {
+        match self {
+            Body::Ascii { words } => {
+                let word: &str = words
+                    .next()
+                    .ok_or_else(|| invalid_data("unexpected end of the PLY data".to_string()))?;
+                let value: f64 = if scalar.is_integer() {
+                    word.parse::<i64>().map(|value| value as f64).ok()
+                } else {
+                    word.parse::<f64>().ok()
+                }
+                .ok_or_else(|| invalid_data(format!("invalid PLY value {:?}", word)))?;
+                Ok(value)
+            }
+            Body::Binary { data, position } => {
+                let bytes: &[u8] = data
+                    .get(*position..*position + scalar.size())
+                    .ok_or_else(|| invalid_data("PLY data is truncated".to_string()))?;
+                *position += scalar.size();
+                Ok(match scalar {
+                    Scalar::Int8 => bytes[0] as i8 as f64,
+                    Scalar::UInt8 => bytes[0] as f64,
+                    Scalar::Int16 => i16::from_le_bytes([bytes[0], bytes[1]]) as f64,
+                    Scalar::UInt16 => u16::from_le_bytes([bytes[0], bytes[1]]) as f64,
+                    Scalar::Int32 => i32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]) as f64,
+                    Scalar::UInt32 => u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]) as f64,
+                    Scalar::Float32 => f32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]) as f64,
+                    Scalar::Float64 => f64::from_le_bytes([
+                        bytes[0], bytes[1], bytes[2], bytes[3], bytes[4], bytes[5], bytes[6], bytes[7],
+                    ]),
+                })
+            }
+        }
+    }
+}
+
+fn read_ply(data: &[u8], material: Arc<dyn Material>) -> io::Result<Mesh> {
+    // The header is text, ending with an "end_header" line
+    let mut header_end: Option<usize> = None;
+    let mut position: usize = 0;
+    let mut lines: Vec<&str> = Vec::new();
+    for line in data.split_inclusive(|&byte| byte == b'\n') {
+        position += line.len();
+        let text: &str = std::str::from_utf8(line)
+            .map_err(|_| invalid_data("PLY header is not text".to_string()))?
+            .trim();
+        if lines.is_empty() && text != "ply" {
+            return Err(invalid_data("missing the PLY magic number".to_string()));
+        }
+        if text == "end_header" {
+            header_end = Some(position);
+            break;
+        }
+        lines.push(text);
+    }
+    let header_end: usize = header_end.ok_or_else(|| invalid_data("PLY header has no end_header".to_string()))?;
+
+    let mut binary: Option<bool> = None;
+    let mut elements: Vec<Element> = Vec::new();
+    for line in lines.iter().skip(1) {
+        let words: Vec<&str> = line.split_whitespace().collect();
+        match words[..] {
+            [] | ["comment", ..] | ["obj_info", ..] => {}
+            ["format", "ascii", _] => binary = Some(false),
+            ["format", "binary_little_endian", _] => binary = Some(true),
+            ["format", format, _] => return Err(invalid_data(format!("unsupported PLY format {:?}", format))),
+            ["element", name, count] => elements.push(Element {
+                name: name.to_string(),
+                count: count
+                    .parse::<usize>()
+                    .map_err(|_| invalid_data(format!("invalid count {:?} for the PLY element {}", count, name)))?,
+                properties: Vec::new(),
+            }),
+            ["property", "list", count, scalar, name] => {
+                let property: Property = Property {
+                    name: name.to_string(),
+                    scalar: Scalar::from_name(scalar)?,
+                    count: Some(Scalar::from_name(count)?),
+                };
+                add_property(&mut elements, property)?;
+            }
+            ["property", scalar, name] => {
+                let property: Property = Property {
+                    name: name.to_string(),
+                    scalar: Scalar::from_name(scalar)?,
+                    count: None,
+                };
+                add_property(&mut elements, property)?;
+            }
+            _ => return Err(invalid_data(format!("malformed PLY header line {:?}", line))),
+        }
+    }
+    let binary: bool = binary.ok_or_else(|| invalid_data("PLY header has no format".to_string()))?;
+
+    let mut body: Body = if binary {
+        Body::Binary {
+            data,
+            position: header_end,
+        }
+    } else {
+        let text: &str = std::str::from_utf8(&data[header_end..])
+            .map_err(|_| invalid_data("ASCII PLY data is not text".to_string()))?;
+        Body::Ascii {
+            words: text.split_ascii_whitespace(),
+        }
+    };
+
+    let mut positions: Vec<Point> = Vec::new();
+    let mut normals: Vec<DVec3> = Vec::new();
+    let mut uvs: Vec<DVec2> = Vec::new();
+    let mut colors: Vec<Color> = Vec::new();
+    let mut indices: Vec<[u32; 3]> = Vec::new();
+
+    for element in elements.iter() {
+        let find = |names: &[&str]| element.properties.iter().position(|property| names.contains(&property.name.as_str()));
+        let is_vertex: bool = element.name == "vertex";
+        let is_face: bool = element.name == "face";
+
+        let position_columns: Option<[usize; 3]> = find(&["x"]).zip(find(&["y"])).zip(find(&["z"])).map(|((x, y), z)| [x, y, z]);
+        let normal_columns: Option<[usize; 3]> = find(&["nx"]).zip(find(&["ny"])).zip(find(&["nz"])).map(|((x, y), z)| [x, y, z]);
+        let uv_columns: Option<[usize; 2]> = find(&["u", "s", "texture_u", "texture_s"])
+            .zip(find(&["v", "t", "texture_v", "texture_t"]))
+            .map(|(u, v)| [u, v]);
+        let color_columns: Option<[usize; 3]> = find(&["red", "r"])
+            .zip(find(&["green", "g"]))
+            .zip(find(&["blue", "b"]))
+            .map(|((r, g), b)| [r, g, b]);
+        let face_column: Option<usize> = find(&["vertex_indices", "vertex_index"]);
+
+        if is_vertex && position_columns.is_none() {
+            return Err(invalid_data("PLY vertices have no x, y and z properties".to_string()));
+        }
+        if is_face && face_column.is_none() {
+            return Err(invalid_data("PLY faces have no vertex_indices property".to_string()));
+        }
+
+        let mut values: Vec<f64> = vec![0.0; element.properties.len()];
+        let mut polygon: Vec<u32> = Vec::new();
+        for index in 0..element.count {
+            // Text can spell out nan and inf, binary floats can hold them
+            let mut finite = |scalar: Scalar, name: &str| -> io::Result<f64> {
+                let value: f64 = body.value(scalar)?;
+                if !value.is_finite() {
+                    return Err(invalid_data(format!(
+                        "PLY {} {} has a non-finite {}: {}",
+                        element.name, index, name, value
+                    )));
+                }
+                Ok(value)
+            };
+            for (column, property) in element.properties.iter().enumerate() {
+                match property.count {
+                    Some(count_scalar) => {
+                        let count: f64 = finite(count_scalar, &property.name)?;
+                        let is_polygon: bool = is_face && face_column == Some(column);
+                        if is_polygon {
+                            polygon.clear();
+                        }
+                        for _ in 0..count as usize {
+                            let value: f64 = finite(property.scalar, &property.name)?;
+                            if is_polygon {
+                                if value < 0.0 || value >= u32::MAX as f64 {
+                                    return Err(invalid_data(format!("invalid PLY vertex index {}", value)));
+                                }
+                                polygon.push(value as u32);
+                            }
+                        }
+                    }
+                    None => values[column] = finite(property.scalar, &property.name)?,
+                }
+            }
+
+            if is_vertex {
+                let [x, y, z] = position_columns.unwrap_or_default();
+                positions.push(Point::new(values[x], values[y], values[z]));
+                if let Some([x, y, z]) = normal_columns {
+                    normals.push(DVec3::new(values[x], values[y], values[z]).normalize_or_zero());
+                }
+                if let Some([u, v]) = uv_columns {
+                    uvs.push(DVec2::new(values[u], values[v]));
+                }
+                if let Some(columns) = color_columns {
+                    // Integer colors are gamma encoded bytes, float ones are already linear in 0..1
+                    let [r, g, b] = columns.map(|column| {
+                        let property: &Property = &element.properties[column];
+                        match property.scalar {
+                            Scalar::UInt8 | Scalar::Int8 => gamma_to_linear(values[column] / 255.0),
+                            Scalar::UInt16 | Scalar::Int16 => gamma_to_linear(values[column] / 65535.0),
+                            _ => values[column],
+                        }
+                    });
+                    colors.push(Color::new(r, g, b));
+                }
+            } else if is_face {
+                if polygon.len() < 3 {
+                    return Err(invalid_data(format!("PLY face with {} vertices", polygon.len())));
+                }
+                for i in 1..polygon.len() - 1 {
+                    indices.push([polygon[0], polygon[i], polygon[i + 1]]);
+                }
+            }
+        }
+    }
+
+    if let Some(index) = indices.iter().flatten().find(|&&index| index as usize >= positions.len()) {
+        return Err(invalid_data(format!(
+            "PLY vertex index {} out of range, {} vertices",
+            index,
+            positions.len()
+        )));
+    }
+
+    let face_materials: Vec<u32> = vec![0; indices.len()];
+    Ok(Mesh::new(positions, normals, uvs, colors, indices, face_materials, vec![material]))
+}
+
+fn add_property(elements: &mut [Element], property: Property) -> io::Result<()> {
+    let element: &mut Element = elements
+        .last_mut()
+        .ok_or_else(|| invalid_data(format!("PLY property {} before any element", property.name)))?;
+    element.properties.push(property);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::models::materials::material::Lambertian;
+
+    use super::*;
+
+    const ASCII_HEADER: &str = "ply\nformat ascii 1.0\nelement vertex 4\nproperty float x\nproperty float y\nproperty float z\n\
+                                element face 1\nproperty list uchar int vertex_indices\nend_header\n";
+    const BINARY_HEADER: &str = "ply\nformat binary_little_endian 1.0\nelement vertex 3\nproperty float x\n\
+                                 property float y\nproperty float z\nelement face 1\nproperty list uchar int vertex_indices\nend_header\n";
+
+    fn parse(data: &[u8]) -> io::Result<Mesh> {
+        read_ply(data, Arc::new(Lambertian::new(Color::ONE)))
+    }
+
+    fn message(data: &[u8]) -> String {
+        parse(data).err().expect("the PLY should be rejected").to_string()
+    }
+
+    // Binary triangle with the given vertex coordinates
+    fn binary(coordinates: [f32; 9]) -> Vec<u8> {
+        let mut data: Vec<u8> = BINARY_HEADER.as_bytes().to_vec();
+        data.extend(coordinates.iter().flat_map(|value| value.to_le_bytes()));
+        data.push(3);
+        data.extend([0_i32, 1, 2].iter().flat_map(|index| index.to_le_bytes()));
+        data
+    }
+
+    #[test]
+    fn quads_are_split_in_triangles() {
+        let text: String = format!("{}0 0 0\n1 0 0\n1 1 0\n0 1 0\n4 0 1 2 3\n", ASCII_HEADER);
+        assert_eq!(parse(text.as_bytes()).unwrap().triangle_count(), 2);
+        assert_eq!(parse(&binary([0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 1.0, 1.0, 0.0])).unwrap().triangle_count(), 1);
+    }
+
+    #[test]
+    fn truncated_binary() {
+        let data: Vec<u8> = binary([0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 1.0, 1.0, 0.0]);
+        assert_eq!(message(&data[..data.len() - 2]), "PLY data is truncated");
+    }
+
+    #[test]
+    fn non_finite_values() {
+        let text: String = format!("{}0 0 0\n1 nan 0\n1 1 0\n0 1 0\n4 0 1 2 3\n", ASCII_HEADER);
+        assert_eq!(message(text.as_bytes()), "PLY vertex 1 has a non-finite y: NaN");
+        let data: Vec<u8> = binary([0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 1.0, 1.0, f32::INFINITY]);
+        assert_eq!(message(&data), "PLY vertex 2 has a non-finite z: inf");
+    }
+}
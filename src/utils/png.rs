@@ -0,0 +1,90 @@
+use std::io::{self, Cursor, Write};
+
+use glam::DVec3 as Color;
+use png::{BitDepth, ColorType, Decoder, Encoder, SrgbRenderingIntent, Transformations};
+
+use crate::models::image::Image;
+
+use super::{
+    color::{linear_to_srgb, srgb_to_linear},
+    ppm::invalid_data,
+};
+
+// PNG tagged as sRGB, with 8 or 16 bits per channel
+pub fn write_png(image: &Image, out: &mut impl Write, sixteen_bit: bool) -> io::Result<()> {
+    let mut encoder: Encoder<&mut _> = Encoder::new(out, image.width as u32, image.height as u32);
+    encoder.set_color(ColorType::Rgb);
+    encoder.set_source_srgb(SrgbRenderingIntent::Perceptual);
+
+    let data: Vec<u8> = if sixteen_bit {
+        encoder.set_depth(BitDepth::Sixteen);
+        image
+            .pixels
+            .iter()
+            .flat_map(|pixel| pixel.to_array())
+            // PNG stores 16 bits samples in big endian
+            .flat_map(|channel| ((linear_to_srgb(channel) * 65535.0).round() as u16).to_be_bytes())
+            .collect()
+    } else {
+        encoder.set_depth(BitDepth::Eight);
+        image
+            .pixels
+            .iter()
+            .flat_map(|pixel| pixel.to_array())
+            .map(|channel| (linear_to_srgb(channel) * 255.0).round() as u8)
+            .collect()
+    };
+
+    let mut writer = encoder.write_header()?;
+    writer.write_image_data(&data)?;
+    writer.finish()?;
+    Ok(())
+}
+
+// Reads an 8 or 16 bits PNG of any color type, the sRGB samples are turned back to linear and alpha is dropped
+pub fn read_png(data: &[u8]) -> io::Result<Image> {
+    let mut decoder: Decoder<Cursor<&[u8]>> = Decoder::new(Cursor::new(data));
+    // Palettes and bit depths under 8 are expanded to plain 8 bits samples
+    decoder.set_transformations(Transformations::EXPAND);
+    let mut reader = decoder.read_info()?;
+    let buffer_size: usize = reader
+        .output_buffer_size()
+        .ok_or_else(|| invalid_data("PNG image is too large".to_string()))?;
+    let mut buffer: Vec<u8> = vec![0; buffer_size];
+    let info: png::OutputInfo = reader.next_frame(&mut buffer)?;
+
+    let channels: usize = match info.color_type {
+        ColorType::Grayscale => 1,
+        ColorType::GrayscaleAlpha => 2,
+        ColorType::Rgb => 3,
+        ColorType::Rgba => 4,
+        ColorType::Indexed => return Err(invalid_data("PNG palette was not expanded".to_string())),
+    };
+    let samples: Vec<f64> = match info.bit_depth {
+        BitDepth::Sixteen => buffer
+            .chunks_exact(2)
+            .map(|bytes| u16::from_be_bytes([bytes[0], bytes[1]]) as f64 / 65535.0)
+            .collect(),
+        BitDepth::Eight => buffer.iter().map(|&byte| byte as f64 / 255.0).collect(),
+        depth => return Err(invalid_data(format!("unsupported PNG bit depth {:?}", depth))),
+    };
+
+    let width: usize = info.width as usize;
+    let height: usize = info.height as usize;
+    let row_samples: usize = width * channels;
+    let mut image: Image = Image::new(width, height);
+    for j in 0..height {
+        // Rows may be padded, each one starts at a multiple of the line size
+        let row_start: usize = j * info.line_size / (info.bit_depth as usize / 8);
+        let row: &[f64] = &samples[row_start..row_start + row_samples];
+        for (i, pixel) in row.chunks_exact(channels).enumerate() {
+            let color: Color = if channels < 3 {
+                Color::splat(srgb_to_linear(pixel[0]))
+            } else {
+                Color::new(srgb_to_linear(pixel[0]), srgb_to_linear(pixel[1]), srgb_to_linear(pixel[2]))
+            };
+            image.set(i, j, color);
+        }
+    }
+    Ok(image)
+}
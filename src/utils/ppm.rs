@@ -0,0 +1,130 @@
+use std::io::{self, Read, Write};
+
+use glam::DVec3 as Color;
+
+use crate::models::image::Image;
+
+use super::color::{convert_to_rgb, gamma_to_linear};
+
+// Plain text PPM, every pixel gamma corrected and clamped to 0..255
+pub fn write_p3(image: &Image, out: &mut impl Write) -> io::Result<()> {
+    writeln!(out, "P3")?;
+    writeln!(out, "{} {}", image.width, image.height)?;
+    writeln!(out, "255")?;
+    for pixel_color in image.pixels.iter() {
+        let [r, g, b] = convert_to_rgb(*pixel_color);
+        writeln!(out, "{} {} {}", r, g, b)?;
+    }
+    out.flush()
+}
+
+// Binary PPM, same pixels as the P3 but one byte per channel
+pub fn write_p6(image: &Image, out: &mut impl Write) -> io::Result<()> {
+    write!(out, "P6\n{} {}\n255\n", image.width, image.height)?;
+    let data: Vec<u8> = image
+        .pixels
+        .iter()
+        .flat_map(|pixel_color| convert_to_rgb(*pixel_color))
+        .collect();
+    out.write_all(&data)?;
+    out.flush()
+}
+
+// Reads a P3 or P6 file, undoing the gamma correction so the pixels are linear again
+pub fn read_ppm(input: &mut impl Read) -> io::Result<Image> {
+    let mut data: Vec<u8> = Vec::new();
+    input.read_to_end(&mut data)?;
+
+    let mut header: HeaderReader = HeaderReader { data: &data, position: 0 };
+    let magic: String = header.token()?;
+    let width: usize = header.number("width")?;
+    let height: usize = header.number("height")?;
+    let max_value: usize = header.number("maximum value")?;
+    if max_value == 0 || max_value > 65535 {
+        return Err(invalid_data(format!("invalid PPM maximum value {}", max_value)));
+    }
+
+    // The size comes from the file, it may not even fit in memory
+    let sample_count: usize = width
+        .checked_mul(height)
+        .and_then(|pixels| pixels.checked_mul(3))
+        .ok_or_else(|| invalid_data(format!("PPM size {}x{} is too large", width, height)))?;
+
+    let samples: Vec<usize> = match magic.as_str() {
+        "P3" => (0..sample_count)
+            .map(|_| header.number("pixel value"))
+            .collect::<io::Result<Vec<usize>>>()?,
+        "P6" => {
+            // A single whitespace separates the header from the binary pixels
+            let start: usize = header.position + 1;
+            let bytes_per_sample: usize = if max_value < 256 { 1 } else { 2 };
+            let end: Option<usize> = sample_count
+                .checked_mul(bytes_per_sample)
+                .and_then(|length| length.checked_add(start));
+            let Some(end) = end.filter(|&end| end <= data.len()) else {
+                return Err(invalid_data("PPM pixel data is truncated".to_string()));
+            };
+            if bytes_per_sample == 1 {
+                data[start..end].iter().map(|&byte| byte as usize).collect()
+            } else {
+                data[start..end]
+                    .chunks_exact(2)
+                    .map(|pair| u16::from_be_bytes([pair[0], pair[1]]) as usize)
+                    .collect()
+            }
+        }
+        _ => return Err(invalid_data(format!("unsupported PPM magic number {:?}", magic))),
+    };
+
+    let to_linear = |sample: usize| -> io::Result<f64> {
+        if sample > max_value {
+            return Err(invalid_data(format!("pixel value {} above the maximum value {}", sample, max_value)));
+        }
+        Ok(gamma_to_linear(sample as f64 / max_value as f64))
+    };
+    let pixels: Vec<Color> = samples
+        .chunks_exact(3)
+        .map(|rgb| Ok(Color::new(to_linear(rgb[0])?, to_linear(rgb[1])?, to_linear(rgb[2])?)))
+        .collect::<io::Result<Vec<Color>>>()?;
+
+    Ok(Image::from_pixels(width, height, pixels))
+}
+
+// Whitespace separated tokens of a netpbm header, skipping the '#' comments
+struct HeaderReader<'a> {
+    data: &'a [u8],
+    position: usize,
+}
+
+impl HeaderReader<'_> {
+    fn token(&mut self) -> io::Result<String> {
+        loop {
+            match self.data.get(self.position) {
+                Some(b'#') => {
+                    while !matches!(self.data.get(self.position), Some(b'\n') | None) {
+                        self.position += 1;
+                    }
+                }
+                Some(byte) if byte.is_ascii_whitespace() => self.position += 1,
+                Some(_) => break,
+                None => return Err(invalid_data("unexpected end of the PPM file".to_string())),
+            }
+        }
+        let start: usize = self.position;
+        while matches!(self.data.get(self.position), Some(byte) if !byte.is_ascii_whitespace()) {
+            self.position += 1;
+        }
+        Ok(String::from_utf8_lossy(&self.data[start..self.position]).into_owned())
+    }
+
+    fn number(&mut self, name: &str) -> io::Result<usize> {
+        let token: String = self.token()?;
+        token
+            .parse::<usize>()
+            .map_err(|_| invalid_data(format!("invalid PPM {} {:?}", name, token)))
+    }
+}
+
+pub(crate) fn invalid_data(message: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
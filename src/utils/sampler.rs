@@ -0,0 +1,33 @@
+use rand::{rngs::StdRng, Rng, SeedableRng};
+
+// Source of every random number used while building and rendering a scene.
+// A render seeds one sampler per pixel from its own seed and the pixel index, so the
+// samples of a pixel never depend on which thread rendered it or in which order.
+pub struct Sampler {
+    rng: StdRng,
+}
+
+impl Sampler {
+    pub fn new(seed: u64) -> Self {
+        Self {
+            rng: StdRng::seed_from_u64(seed),
+        }
+    }
+
+    pub fn for_pixel(seed: u64, i: i32, j: i32) -> Self {
+        let pixel: u64 = ((j as u32 as u64) << 32) | i as u32 as u64;
+        Self::new(mix(seed ^ mix(pixel)))
+    }
+
+    pub fn random_float(&mut self) -> f64 {
+        self.rng.gen_range(0.0..1.0)
+    }
+}
+
+// SplitMix64 finalizer, spreads neighbouring pixel indexes over the whole seed space
+fn mix(mut z: u64) -> u64 {
+    z = z.wrapping_add(0x9E37_79B9_7F4A_7C15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    z ^ (z >> 31)
+}
@@ -0,0 +1,184 @@
+use std::{collections::HashMap, fs, io, path::Path, sync::Arc};
+
+use glam::DVec3 as Point;
+
+use crate::models::{materials::material::Material, objects::mesh::Mesh};
+
+use super::ppm::invalid_data;
+
+// Loads an STL file, ASCII or binary. STL stores every triangle with its own three vertices,
+// identical positions are merged so the mesh stays indexed. The facet normals are ignored,
+// the triangles are flat shaded with their geometric normal anyway.
+pub fn load_stl(path: &Path, material: Arc<dyn Material>) -> io::Result<Mesh> {
+    let data: Vec<u8> = fs::read(path)
+        .map_err(|error| io::Error::new(error.kind(), format!("{}: {}", path.display(), error)))?;
+    read_stl(&data, path, material)
+}
+
+// `path` only locates the errors
+fn read_stl(data: &[u8], path: &Path, material: Arc<dyn Material>) -> io::Result<Mesh> {
+    // Binary files may also start with "solid", their size matching the triangle count is what tells them apart
+    let binary_size: Option<usize> = data
+        .get(80..84)
+        .map(|count| 84 + 50 * u32::from_le_bytes([count[0], count[1], count[2], count[3]]) as usize);
+    let triangles: Vec<[Point; 3]> = if binary_size == Some(data.len()) || !data.trim_ascii_start().starts_with(b"solid") {
+        read_binary_stl(data).map_err(|error| io::Error::new(error.kind(), format!("{}: {}", path.display(), error)))?
+    } else {
+        read_ascii_stl(data, path)?
+    };
+
+    let mut slots: HashMap<[u64; 3], u32> = HashMap::new();
+    let mut positions: Vec<Point> = Vec::new();
+    let mut indices: Vec<[u32; 3]> = Vec::with_capacity(triangles.len());
+    for triangle in triangles.iter() {
+        indices.push(triangle.map(|vertex| {
+            *slots.entry(vertex.to_array().map(f64::to_bits)).or_insert_with(|| {
+                positions.push(vertex);
+                (positions.len() - 1) as u32
+            })
+        }));
+    }
+
+    let face_materials: Vec<u32> = vec![0; indices.len()];
+    Ok(Mesh::new(positions, Vec::new(), Vec::new(), Vec::new(), indices, face_materials, vec![material]))
+}
+
+// 80 bytes of header, the triangle count, then 50 bytes per triangle:
+// normal and three vertices as little endian f32, and a 2 bytes attribute
+fn read_binary_stl(data: &[u8]) -> io::Result<Vec<[Point; 3]>> {
+    let count_bytes: &[u8] = data
+        .get(80..84)
+        .ok_or_else(|| invalid_data("binary STL header is truncated".to_string()))?;
+    let count: usize = u32::from_le_bytes([count_bytes[0], count_bytes[1], count_bytes[2], count_bytes[3]]) as usize;
+    let records: &[u8] = &data[84..];
+    if records.len() < count * 50 {
+        return Err(invalid_data(format!(
+            "binary STL is truncated, {} triangles announced but only {} bytes of data",
+            count,
+            records.len()
+        )));
+    }
+
+    let read_point = |bytes: &[u8]| -> Point {
+        let [x, y, z] = [0, 4, 8].map(|offset| {
+            f32::from_le_bytes([bytes[offset], bytes[offset + 1], bytes[offset + 2], bytes[offset + 3]]) as f64
+        });
+        Point::new(x, y, z)
+    };
+    let triangles: Vec<[Point; 3]> = records
+        .chunks_exact(50)
+        .take(count)
+        .map(|record| [read_point(&record[12..24]), read_point(&record[24..36]), read_point(&record[36..48])])
+        .collect();
+    if let Some(index) = triangles.iter().position(|triangle| !triangle.iter().all(|vertex| vertex.is_finite())) {
+        return Err(invalid_data(format!("binary STL triangle {} has a non finite vertex", index)));
+    }
+    Ok(triangles)
+}
+
+// solid <name> / facet normal <n> / outer loop / vertex <p> x3 / endloop / endfacet / endsolid
+fn read_ascii_stl(data: &[u8], path: &Path) -> io::Result<Vec<[Point; 3]>> {
+    let text: &str = std::str::from_utf8(data)
+        .map_err(|_| invalid_data(format!("{}: ASCII STL is not text", path.display())))?;
+    let located = |line: usize, message: String| invalid_data(format!("{}:{}: {}", path.display(), line, message));
+
+    let mut triangles: Vec<[Point; 3]> = Vec::new();
+    let mut facet: Option<Vec<Point>> = None;
+    for (index, line) in text.lines().enumerate() {
+        let line_number: usize = index + 1;
+        let words: Vec<&str> = line.split_whitespace().collect();
+        match words[..] {
+            [] | ["solid", ..] | ["endsolid", ..] | ["outer", "loop"] | ["endloop"] => {}
+            ["facet", "normal", _, _, _] => {
+                if facet.is_some() {
+                    return Err(located(line_number, "facet inside another facet".to_string()));
+                }
+                facet = Some(Vec::new());
+            }
+            ["vertex", x, y, z] => {
+                let vertices: &mut Vec<Point> = facet
+                    .as_mut()
+                    .ok_or_else(|| located(line_number, "vertex outside of a facet".to_string()))?;
+                let coordinates: Vec<f64> = [x, y, z]
+                    .iter()
+                    .map(|word| word.parse::<f64>().ok().filter(|value| value.is_finite()))
+                    .collect::<Option<Vec<f64>>>()
+                    .ok_or_else(|| located(line_number, format!("invalid vertex {:?}", line.trim())))?;
+                vertices.push(Point::new(coordinates[0], coordinates[1], coordinates[2]));
+            }
+            ["endfacet"] => {
+                let vertices: Vec<Point> = facet
+                    .take()
+                    .ok_or_else(|| located(line_number, "endfacet without a facet".to_string()))?;
+                if vertices.len() < 3 {
+                    return Err(located(line_number, format!("facet with {} vertices", vertices.len())));
+                }
+                // Some exporters write polygons, they are split in triangles like OBJ faces
+                for i in 1..vertices.len() - 1 {
+                    triangles.push([vertices[0], vertices[i], vertices[i + 1]]);
+                }
+            }
+            _ => return Err(located(line_number, format!("malformed STL line {:?}", line.trim()))),
+        }
+    }
+    if facet.is_some() {
+        return Err(invalid_data(format!("{}: ASCII STL ends inside a facet", path.display())));
+    }
+    Ok(triangles)
+}
+
+#[cfg(test)]
+mod tests {
+    use glam::DVec3 as Color;
+
+    use crate::models::materials::material::Lambertian;
+
+    use super::*;
+
+    fn parse(data: &[u8]) -> io::Result<Mesh> {
+        read_stl(data, Path::new("test.stl"), Arc::new(Lambertian::new(Color::ONE)))
+    }
+
+    // Binary file of a square made of two triangles sharing an edge
+    fn binary(header: &[u8], count: u32) -> Vec<u8> {
+        let mut data: Vec<u8> = header.to_vec();
+        data.resize(80, b' ');
+        data.extend(count.to_le_bytes());
+        for triangle in [[[0.0_f32, 0.0, 0.0], [1.0, 0.0, 0.0], [1.0, 1.0, 0.0]], [[0.0, 0.0, 0.0], [1.0, 1.0, 0.0], [0.0, 1.0, 0.0]]] {
+            data.extend([0.0_f32, 0.0, 1.0].iter().flat_map(|value| value.to_le_bytes()));
+            data.extend(triangle.iter().flatten().flat_map(|value| value.to_le_bytes()));
+            data.extend([0, 0]);
+        }
+        data
+    }
+
+    #[test]
+    fn binary_triangles_share_vertices() {
+        let mesh: Mesh = parse(&binary(b"binary square", 2)).unwrap();
+        assert_eq!(mesh.triangle_count(), 2);
+        assert_eq!(mesh.positions.len(), 4);
+    }
+
+    #[test]
+    fn binary_header_starting_with_solid() {
+        assert_eq!(parse(&binary(b"solid square", 2)).unwrap().triangle_count(), 2);
+    }
+
+    #[test]
+    fn truncated_binary() {
+        let error: io::Error = parse(&binary(b"binary square", 3)).err().expect("the STL should be rejected");
+        assert_eq!(
+            error.to_string(),
+            "test.stl: binary STL is truncated, 3 triangles announced but only 100 bytes of data"
+        );
+    }
+
+    #[test]
+    fn ascii_facets() {
+        let text: &str = "solid square\nfacet normal 0 0 1\nouter loop\nvertex 0 0 0\nvertex 1 0 0\nvertex 1 1 0\nvertex 0 1 0\n\
+                          endloop\nendfacet\nendsolid square\n";
+        assert_eq!(parse(text.as_bytes()).unwrap().triangle_count(), 2);
+        let error: io::Error = parse(b"solid square\nfacet normal 0 0 1\nouter loop\nvertex 0 0\n").err().unwrap();
+        assert_eq!(error.to_string(), "test.stl:4: malformed STL line \"vertex 0 0\"");
+    }
+}
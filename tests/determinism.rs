@@ -0,0 +1,54 @@
+use rt::models::{
+    image::Image,
+    scene::{parse_scene, Scene},
+};
+
+// Small scene with a bit of everything that draws random numbers: diffuse and fuzzy bounces, glass,
+// a sampled light and a volume
+const SCENE: &str = "
+camera width 24
+camera aspect_ratio 1
+camera fov 40
+camera background 0.1 0.1 0.1
+camera look_from 0 1 6
+camera look_at 0 0.5 0
+camera samples_per_pixel 8
+camera max_depth 8
+camera seed 7
+material white lambertian 0.7 0.7 0.7
+material steel metal 0.8 0.8 0.8 0.3
+material glass dielectric 1.5
+material light diffuse_light 6 6 6
+quad -3 0 -3    6 0 0    0 0 6    white
+sphere -1 0.5 0    0.5    steel
+sphere 1 0.5 0    0.5    glass
+quad -1 3 -1    2 0 0    0 0 2    light
+medium 0.5    0.9 0.9 0.9    sphere 0 0.4 1    0.4    white
+";
+
+fn render(threads: usize, seed: u64) -> Image {
+    let mut scene: Scene = parse_scene(SCENE).unwrap();
+    scene.camera.threads = threads;
+    scene.camera.tile_size = 8;
+    scene.camera.seed = seed;
+    scene.camera.render(&scene.world)
+}
+
+fn bits(image: &Image) -> Vec<[u64; 3]> {
+    image.pixels.iter().map(|pixel| pixel.to_array().map(f64::to_bits)).collect()
+}
+
+#[test]
+fn same_seed_gives_the_same_image() {
+    assert_eq!(bits(&render(2, 7)), bits(&render(2, 7)));
+}
+
+#[test]
+fn thread_count_does_not_change_the_image() {
+    assert_eq!(bits(&render(1, 7)), bits(&render(4, 7)));
+}
+
+#[test]
+fn seed_changes_the_image() {
+    assert_ne!(bits(&render(1, 7)), bits(&render(1, 8)));
+}
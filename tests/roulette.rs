@@ -0,0 +1,40 @@
+use rt::models::scene::{parse_scene, Scene};
+
+// Closed diffuse box with a small light inside: the light bounces many times before fading, so Russian
+// roulette ends most paths long before the max depth
+const SCENE: &str = "
+camera width 4
+camera aspect_ratio 1
+camera fov 60
+camera background 0 0 0
+camera look_from 0 0 0.9
+camera look_at 0 0 0
+camera samples_per_pixel 300
+camera max_depth 50
+camera seed 3
+material white lambertian 0.8 0.8 0.8
+material light diffuse_light 4 4 4
+box -1 -1 -1    1 1 1    white
+sphere 0 0.7 0    0.15    light
+";
+
+fn mean(roulette_depth: &str) -> f64 {
+    let scene: Scene = parse_scene(&format!("{}camera roulette_depth {}\n", SCENE, roulette_depth)).unwrap();
+    let pixels = scene.camera.render(&scene.world).pixels;
+    pixels.iter().map(|pixel| pixel.element_sum() / 3.0).sum::<f64>() / pixels.len() as f64
+}
+
+#[test]
+fn roulette_keeps_the_mean() {
+    let without_roulette: f64 = mean("50");
+    let with_roulette: f64 = mean("2");
+    let difference: f64 = (with_roulette - without_roulette).abs() / without_roulette;
+    assert!(difference < 0.03, "{} with roulette, {} without", with_roulette, without_roulette);
+}
+
+#[test]
+fn negative_depths_are_rejected() {
+    for setting in ["roulette_depth -1", "max_depth -5"] {
+        assert!(parse_scene(&format!("camera {}\n", setting)).is_err(), "{}", setting);
+    }
+}